@@ -0,0 +1,53 @@
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use common::{Grid, Rng};
+use raycast_dda::RayCastEngine;
+use std::time::{Duration, Instant};
+
+const MAP_SIZE: (usize, usize) = (1024, 1024);
+const RAYS: usize = 100_000;
+
+/// casts every ray a few times and keeps the fastest time to cut down on noise
+fn cast_all(engine: &RayCastEngine<Grid>, rays: &[((f32, f32), f32)]) -> Duration {
+    let mut best = Duration::MAX;
+    for _ in 0..3 {
+        let start = Instant::now();
+        for (pos, angle) in rays {
+            std::hint::black_box(engine.cast_ray(*pos, *angle, 2048.));
+        }
+        best = best.min(start.elapsed());
+    }
+    best
+}
+
+fn main() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+
+    for (name, density) in [("open", 0.0), ("sparse", 0.0005), ("scattered", 0.005), ("dense", 0.05)] {
+        let mut engine = RayCastEngine::new(Grid::random(MAP_SIZE, density, &mut rng), MAP_SIZE);
+
+        let rays: Vec<((f32, f32), f32)> = (0..RAYS)
+            .map(|_| {
+                let pos = (1. + rng.next() * (MAP_SIZE.0 - 2) as f32, 1. + rng.next() * (MAP_SIZE.1 - 2) as f32);
+                (pos, rng.next() * std::f32::consts::TAU)
+            })
+            .collect();
+
+        let plain_time = cast_all(&engine, &rays);
+
+        let build_start = Instant::now();
+        engine.build_distance_field();
+        let build_time = build_start.elapsed();
+
+        let field_time = cast_all(&engine, &rays);
+
+        println!(
+            "{name:>9}: plain {:>8.2?}  distance field {:>8.2?} (built in {:>8.2?})  {:.2}x faster",
+            plain_time,
+            field_time,
+            build_time,
+            plain_time.as_secs_f64() / field_time.as_secs_f64(),
+        );
+    }
+}
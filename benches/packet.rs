@@ -0,0 +1,79 @@
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use common::{Grid, Rng};
+use raycast_dda::{RayCastEngine, RayData};
+use std::time::{Duration, Instant};
+
+const MAP_SIZE: (usize, usize) = (128, 128);
+const FRAMES: usize = 200;
+const MAX_DISTANCE: f32 = 256.;
+const FOV: f32 = std::f32::consts::FRAC_PI_3;
+
+/// casts the rays for one view from a position
+type Cast<'a> = &'a dyn Fn((f32, f32), &[f32]) -> Vec<RayData>;
+
+/// the angle of every column on the screen for a view looking at `angle`
+fn column_angles(angle: f32, columns: usize) -> Vec<f32> {
+    (0..columns).map(|x| angle - FOV / 2. + FOV * x as f32 / columns as f32).collect()
+}
+
+/// casts every frame with each way of casting a few times, taking turns so they all see the same
+/// noise, and keeps the fastest time for each
+fn time<const M: usize>(frames: &[((f32, f32), Vec<f32>)], casts: [Cast; M]) -> [Duration; M] {
+    let mut results = [Duration::MAX; M];
+    for _ in 0..5 {
+        for (cast, best) in casts.iter().zip(results.iter_mut()) {
+            let start = Instant::now();
+            for (pos, angles) in frames {
+                std::hint::black_box(cast(*pos, angles));
+            }
+            *best = (*best).min(start.elapsed());
+        }
+    }
+    results
+}
+
+fn packets<const N: usize>(engine: &RayCastEngine<Grid>, pos: (f32, f32), angles: &[f32]) -> Vec<RayData> {
+    let mut rays = Vec::with_capacity(angles.len());
+    for chunk in angles.chunks(N) {
+        match N {
+            4 if chunk.len() == 4 => rays.extend(engine.cast_ray_packet4(pos, chunk.try_into().unwrap(), MAX_DISTANCE)),
+            8 if chunk.len() == 8 => rays.extend(engine.cast_ray_packet8(pos, chunk.try_into().unwrap(), MAX_DISTANCE)),
+            _ => rays.extend(chunk.iter().map(|angle| engine.cast_ray(pos, *angle, MAX_DISTANCE))),
+        }
+    }
+    rays
+}
+
+fn main() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    let engine = RayCastEngine::new(Grid::random(MAP_SIZE, 0.002, &mut rng), MAP_SIZE);
+
+    for columns in [1280, 3840] {
+        let frames: Vec<((f32, f32), Vec<f32>)> = (0..FRAMES)
+            .map(|_| {
+                let pos = (1. + rng.next() * (MAP_SIZE.0 - 2) as f32, 1. + rng.next() * (MAP_SIZE.1 - 2) as f32);
+                (pos, column_angles(rng.next() * std::f32::consts::TAU, columns))
+            })
+            .collect();
+
+        let [scalar_time, time_4, time_8] = time(
+            &frames,
+            [
+                &|pos, angles| angles.iter().map(|angle| engine.cast_ray(pos, *angle, MAX_DISTANCE)).collect(),
+                &|pos, angles| packets::<4>(&engine, pos, angles),
+                &|pos, angles| packets::<8>(&engine, pos, angles),
+            ],
+        );
+
+        println!(
+            "{columns:>5} columns: scalar {:>8.2?}  packets of 4 {:>8.2?} ({:.2}x)  packets of 8 {:>8.2?} ({:.2}x)",
+            scalar_time,
+            time_4,
+            scalar_time.as_secs_f64() / time_4.as_secs_f64(),
+            time_8,
+            scalar_time.as_secs_f64() / time_8.as_secs_f64(),
+        );
+    }
+}
@@ -0,0 +1,153 @@
+use crate::{DdaWalker, Float, Map, RayCastEngine};
+
+/// how many cells away the nearest cell a ray has to stop at is from each cell of a map, counting
+/// diagonal steps as one (the chebyshev distance). rays stop at walls, portals and the edge of the
+/// map, so a cell with a distance of `d` is in the middle of an empty square of cells `d - 1`
+/// cells across each way that rays can jump straight over. distances stop at 255 to keep the field small
+pub struct DistanceField {
+    size: (usize, usize),
+    distances: Vec<u8>,
+}
+
+impl DistanceField {
+    /// builds the field for a map, it has to be built again after the map changes
+    pub fn new<M: Map>(map: &M, map_size: (usize, usize)) -> Self {
+        let (width, height) = map_size;
+        let mut distances = vec![0; width * height];
+
+        for y in 0..height {
+            for x in 0..width {
+                let empty = map.get_cell(x, y) == Some(0) && map.get_portal(x, y).is_none();
+
+                // the cells just outside of the map are as far away as the nearest edge
+                distances[y * width + x] = if empty {
+                    (x + 1).min(y + 1).min(width - x).min(height - y).min(u8::MAX as usize) as u8
+                } else {
+                    0
+                };
+            }
+        }
+
+        // spreads the distances out from the top left and then from the bottom right,
+        // every cell only has to look at the neighbours that have already been done
+        for y in 0..height {
+            for x in 0..width {
+                let mut distance = distances[y * width + x];
+                for (dx, dy) in [(-1, 0), (-1, -1), (0, -1), (1, -1)] {
+                    if let Some(neighbour) = neighbour(map_size, x, y, dx, dy) {
+                        distance = distance.min(distances[neighbour].saturating_add(1));
+                    }
+                }
+                distances[y * width + x] = distance;
+            }
+        }
+        for y in (0..height).rev() {
+            for x in (0..width).rev() {
+                let mut distance = distances[y * width + x];
+                for (dx, dy) in [(1, 0), (1, 1), (0, 1), (-1, 1)] {
+                    if let Some(neighbour) = neighbour(map_size, x, y, dx, dy) {
+                        distance = distance.min(distances[neighbour].saturating_add(1));
+                    }
+                }
+                distances[y * width + x] = distance;
+            }
+        }
+
+        Self { size: map_size, distances }
+    }
+
+    /// the distance of a cell, cells outside of the map are always 0
+    pub fn get(&self, cell: (i32, i32)) -> u8 {
+        if cell.0 < 0 || cell.1 < 0 || cell.0 as usize >= self.size.0 || cell.1 as usize >= self.size.1 {
+            return 0;
+        }
+        self.distances[cell.1 as usize * self.size.0 + cell.0 as usize]
+    }
+}
+
+/// the index of the neighbour of a cell, or None if it is outside of the map
+fn neighbour(map_size: (usize, usize), x: usize, y: usize, dx: i32, dy: i32) -> Option<usize> {
+    let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+    if nx < 0 || ny < 0 || nx as usize >= map_size.0 || ny as usize >= map_size.1 {
+        return None;
+    }
+    Some(ny as usize * map_size.0 + nx as usize)
+}
+
+impl<T: Map, F: Float> RayCastEngine<T, F> {
+    /// builds a distance field for the map so `cast_ray` can skip over empty space,
+    /// this has to be called again after changing the map
+    pub fn build_distance_field(&mut self) {
+        self.distance_field = Some(DistanceField::new(&self.map, self.map_size));
+    }
+
+    /// steps the ray into the next cell it needs to look at, jumping over empty space when there is
+    /// a distance field. returns the same as `DdaWalker::step` would for that cell
+    pub(crate) fn advance(&self, walker: &mut DdaWalker<F>, max_distance: F) -> (F, bool) {
+        if let Some(field) = &self.distance_field {
+            // jumping over small squares costs more than stepping through them
+            let distance = field.get(walker.cell);
+            if distance >= 4 {
+                if let Some(skipped) = walker.skip((distance - 1) as u32, max_distance) {
+                    return skipped;
+                }
+            }
+        }
+        walker.step()
+    }
+}
+
+impl<F: Float> DdaWalker<F> {
+    /// moves the ray out of the empty square of cells `radius` cells each way around the current cell in
+    /// one go, ending up exactly where stepping through it one cell at a time would. returns the length of
+    /// the ray at the edge it left the square through and if that edge is vertical, or None without moving
+    /// if that is past `max_distance` since the ray would have stopped inside the square
+    pub(crate) fn skip(&mut self, radius: u32, max_distance: F) -> Option<(F, bool)> {
+        // the ray leaves the square on its next `radius + 1`th crossing along one of the axes
+        let exit_x = self.crossing_length(self.crossed.0 + radius, true);
+        let exit_y = self.crossing_length(self.crossed.1 + radius, false);
+
+        // steps along x happen first when they are strictly shorter, the same as `step`
+        let vertical = exit_x < exit_y;
+        let exit = if vertical { exit_x } else { exit_y };
+        if exit >= max_distance {
+            return None;
+        }
+
+        // counts the crossings along the other axis that happen before the ray leaves
+        if vertical {
+            let crossed = self.crossings_before(exit, false, |length| length <= exit);
+            self.cell = (self.cell.0 + self.step.0 * (radius + 1) as i32, self.cell.1 + self.step.1 * crossed as i32);
+            self.crossed = (self.crossed.0 + radius + 1, self.crossed.1 + crossed);
+        } else {
+            let crossed = self.crossings_before(exit, true, |length| length < exit);
+            self.cell = (self.cell.0 + self.step.0 * crossed as i32, self.cell.1 + self.step.1 * (radius + 1) as i32);
+            self.crossed = (self.crossed.0 + crossed, self.crossed.1 + radius + 1);
+        }
+
+        self.ray_length_1d = (self.crossing_length(self.crossed.0, true), self.crossing_length(self.crossed.1, false));
+        Some((exit, vertical))
+    }
+
+    /// how many more edges along one axis the ray crosses at lengths where `before` is true
+    fn crossings_before(&self, exit: F, vertical: bool, before: impl Fn(F) -> bool) -> u32 {
+        let (crossed, unit) = if vertical {
+            (self.crossed.0, self.ray_unit_step_size.0)
+        } else {
+            (self.crossed.1, self.ray_unit_step_size.1)
+        };
+
+        // guesses from the lengths and then fixes the guess so it agrees with the lengths
+        // exactly, rays going along the axis dont cross any of its edges
+        let next = self.crossing_length(crossed, vertical);
+        let mut count = ((exit - next) / unit + F::ONE).max(F::ZERO).to_u32();
+        while count > 0 && !before(self.crossing_length(crossed + count - 1, vertical)) {
+            count -= 1;
+        }
+        while before(self.crossing_length(crossed + count, vertical)) {
+            count += 1;
+        }
+        count
+    }
+}
+
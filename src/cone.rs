@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+use std::f32::consts::{FRAC_PI_2, TAU};
+use std::ops::Neg;
+
+use crate::{Map, RayCastEngine};
+
+/// how close the angles of two parts of the same face have to be for them to be joined together
+const ANGLE_TOLERANCE: f32 = 1e-5;
+
+/// a cell that can be seen from the apex of a cone
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ConeCell {
+    pub cell: (i32, i32),
+
+    /// the value in the map cell, walls are above 0
+    pub val: u32,
+
+    /// how far away the nearest part of the cell that can be seen is
+    pub distance: f32,
+}
+
+/// a part of a wall face that can be seen from the apex of a cone, a face
+/// that is partly hidden behind other walls can be split into more than one part
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ConeFace {
+    /// the wall cell the face is on and the value in it
+    pub cell: (i32, i32),
+    pub val: u32,
+
+    /// a unit vector pointing out of the face, back towards the apex
+    pub normal: (f32, f32),
+
+    /// the angles from the apex that the part is seen between, the smaller one first
+    pub angles: (f32, f32),
+
+    /// the ends of the part in world space, at each of the angles
+    pub points: ((f32, f32), (f32, f32)),
+}
+
+/// everything that can be seen inside of a cone
+#[derive(Clone, Default, Debug)]
+pub struct ConeCast {
+    /// every cell that can be seen at least part of, nearest first. walls are included
+    pub cells: Vec<ConeCell>,
+
+    /// every part of a wall face that can be seen, ordered by angle
+    pub faces: Vec<ConeFace>,
+}
+
+impl<T: Map + std::marker::Sync> RayCastEngine<T> {
+    /// finds every cell and wall face that can be seen in a cone with its apex at `pos`, centered on `angle`
+    /// and `arc` wide, out to `max_distance`. unlike casting lots of rays nothing small is missed between them,
+    /// things that are partly hidden behind walls are still found with only the parts that can be seen given.
+    /// useful for flashlights, searchlights and how far sounds carry. an arc of a whole turn sees all the way
+    /// around. walls always fill their whole cell, portals and mirrors are ignored and the edge of the map
+    /// hides everything past it. the wall the apex is in doesnt hide anything, the same as `cast_ray`
+    pub fn cast_cone(&self, pos: (f32, f32), angle: f32, arc: f32, max_distance: f32) -> ConeCast {
+        let arc = arc.min(TAU);
+        let (start, end) = (angle - arc / 2., angle + arc / 2.);
+        let apex = self.cell_at_point(pos);
+
+        let mut cone = ConeCast::default();
+        let mut seen = HashMap::new();
+
+        // rays going through a quarter of a turn only move away from the apex along both axes, so the cone
+        // is split into quarters that each walk out from the apex one diagonal of cells at a time. cells are
+        // only ever hidden by cells in earlier diagonals, which have all been found by the time they are reached
+        for quarter in (start / FRAC_PI_2).floor() as i32..=(end / FRAC_PI_2).floor() as i32 {
+            let base = quarter as f32 * FRAC_PI_2;
+
+            // the angles that havent been hidden yet, measured from the start of the quarter
+            let mut visible: Vec<(f32, f32)> = vec![((start - base).max(0.), (end - base).min(FRAC_PI_2))];
+            visible.retain(|(lo, hi)| lo < hi);
+
+            // points and directions are turned so the quarter goes from +x to +y
+            let local = |point: (f32, f32)| rotate(point, -quarter);
+
+            for diagonal in 0.. {
+                if visible.is_empty() {
+                    break;
+                }
+
+                let mut in_range = false;
+                for i in 0..=diagonal {
+                    let offset = rotate((i, diagonal - i), quarter);
+                    let cell = (apex.0 + offset.0, apex.1 + offset.1);
+
+                    // the corners of the cell from the apex, only the part of it inside of the quarter is looked at
+                    let corners = [
+                        local((self.origin.0 + cell.0 as f32 * self.cell_size.0 - pos.0, self.origin.1 + cell.1 as f32 * self.cell_size.1 - pos.1)),
+                        local((self.origin.0 + (cell.0 + 1) as f32 * self.cell_size.0 - pos.0, self.origin.1 + (cell.1 + 1) as f32 * self.cell_size.1 - pos.1)),
+                    ];
+                    let min = (corners[0].0.min(corners[1].0), corners[0].1.min(corners[1].1));
+                    let max = (corners[0].0.max(corners[1].0), corners[0].1.max(corners[1].1));
+                    let near = (min.0.max(0.), min.1.max(0.));
+
+                    // cells in later diagonals are always farther away than ones in this diagonal
+                    if near.0.hypot(near.1) > max_distance {
+                        continue;
+                    }
+                    in_range = true;
+
+                    // an apex right on the edge of its cell only touches it
+                    if max.0 <= 0. || max.1 <= 0. {
+                        continue;
+                    }
+
+                    // the angles the cell covers go from its corner nearest +x to its corner nearest +y
+                    let val = self.cell_at(cell);
+                    let covers = (near.1.atan2(max.0), max.1.atan2(near.0));
+                    let parts = overlap(&visible, covers);
+
+                    if let Some(val) = val {
+                        // the nearest point of each part is towards the nearest corner if it can be
+                        let towards = near.1.atan2(near.0);
+                        let distance = parts.iter().map(|part| entry_distance(near, towards.clamp(part.0, part.1))).fold(f32::INFINITY, f32::min);
+
+                        if distance <= max_distance {
+                            let index = *seen.entry(cell).or_insert_with(|| {
+                                cone.cells.push(ConeCell { cell, val, distance });
+                                cone.cells.len() - 1
+                            });
+                            cone.cells[index].distance = cone.cells[index].distance.min(distance);
+                        }
+
+                        if val > 0 && cell != apex {
+                            // the faces that look back along each axis, faces the apex is level with cant be seen
+                            let mut add_faces = |normal: (f32, f32), angles: (f32, f32), along: f32, dir: fn(f32) -> f32| {
+                                for part in overlap(&parts, angles) {
+                                    let world = (base + part.0, base + part.1);
+                                    let point = |angle: f32, local: f32| {
+                                        let length = along / dir(local);
+                                        (pos.0 + angle.cos() * length, pos.1 + angle.sin() * length)
+                                    };
+                                    cone.faces.push(ConeFace {
+                                        cell,
+                                        val,
+                                        normal: rotate(normal, quarter),
+                                        angles: world,
+                                        points: (point(world.0, part.0), point(world.1, part.1)),
+                                    });
+                                }
+                            };
+
+                            // only the part of a face that is closer than the max distance is kept
+                            if min.0 > 0. && min.0 <= max_distance {
+                                let reach = (max_distance * max_distance - min.0 * min.0).sqrt().atan2(min.0);
+                                add_faces((-1., 0.), (near.1.atan2(min.0), max.1.atan2(min.0).min(reach)), min.0, f32::cos);
+                            }
+                            if min.1 > 0. && min.1 <= max_distance {
+                                let reach = min.1.atan2((max_distance * max_distance - min.1 * min.1).sqrt());
+                                add_faces((0., -1.), (min.1.atan2(max.0).max(reach), min.1.atan2(near.0)), min.1, f32::sin);
+                            }
+                        }
+                    }
+
+                    // cells outside of the map hide everything behind them the same as walls
+                    if cell != apex && val.is_none_or(|val| val > 0) {
+                        visible = visible
+                            .iter()
+                            .flat_map(|&(lo, hi)| [(lo, hi.min(covers.0)), (lo.max(covers.1), hi)])
+                            .filter(|(lo, hi)| lo < hi)
+                            .collect();
+                    }
+                }
+
+                if !in_range {
+                    break;
+                }
+            }
+        }
+
+        // faces crossing from one quarter into the next are joined back together
+        cone.faces.sort_by(|a, b| (a.cell, a.normal, a.angles.0).partial_cmp(&(b.cell, b.normal, b.angles.0)).unwrap());
+        cone.faces.dedup_by(|next, face| {
+            let joined = next.cell == face.cell && next.normal == face.normal && next.angles.0 <= face.angles.1 + ANGLE_TOLERANCE;
+            if joined {
+                face.angles.1 = next.angles.1;
+                face.points.1 = next.points.1;
+            }
+            joined
+        });
+
+        cone.faces.sort_by(|a, b| a.angles.0.total_cmp(&b.angles.0));
+        cone.cells.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        cone
+    }
+}
+
+/// turns a vector around by quarter turns, each one going from +x towards +y
+fn rotate<N: Neg<Output = N> + Copy>(v: (N, N), turns: i32) -> (N, N) {
+    match turns.rem_euclid(4) {
+        0 => v,
+        1 => (-v.1, v.0),
+        2 => (-v.0, -v.1),
+        _ => (v.1, -v.0),
+    }
+}
+
+/// the parts of the visible angles that are also between the given angles
+fn overlap(visible: &[(f32, f32)], angles: (f32, f32)) -> Vec<(f32, f32)> {
+    visible
+        .iter()
+        .map(|&(lo, hi)| (lo.max(angles.0), hi.min(angles.1)))
+        .filter(|(lo, hi)| lo < hi)
+        .collect()
+}
+
+/// how far a ray at an angle inside of the quarter goes before it reaches a cell with its nearest corner at `near`
+fn entry_distance(near: (f32, f32), angle: f32) -> f32 {
+    let (sin, cos) = angle.sin_cos();
+    let along = |near: f32, dir: f32| if near > 0. { near / dir } else { 0. };
+    along(near.0, cos).max(along(near.1, sin))
+}
@@ -0,0 +1,293 @@
+use macroquad::prelude::*;
+use serde_json::{json, Map as JsonMap, Value};
+use std::fs;
+
+/// the file the key bindings are loaded from and saved to
+pub const CONTROLS_FILE: &str = "controls.json";
+
+/// every action the player can bind a key to
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    Forward,
+    Backward,
+    StrafeLeft,
+    StrafeRight,
+    TurnLeft,
+    TurnRight,
+    LookUp,
+    LookDown,
+    Sprint,
+    Jump,
+    Crouch,
+}
+
+impl Action {
+    pub const ALL: [Action; 11] = [
+        Action::Forward,
+        Action::Backward,
+        Action::StrafeLeft,
+        Action::StrafeRight,
+        Action::TurnLeft,
+        Action::TurnRight,
+        Action::LookUp,
+        Action::LookDown,
+        Action::Sprint,
+        Action::Jump,
+        Action::Crouch,
+    ];
+
+    /// the name used for the action in the controls file
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Forward => "forward",
+            Action::Backward => "backward",
+            Action::StrafeLeft => "strafe_left",
+            Action::StrafeRight => "strafe_right",
+            Action::TurnLeft => "turn_left",
+            Action::TurnRight => "turn_right",
+            Action::LookUp => "look_up",
+            Action::LookDown => "look_down",
+            Action::Sprint => "sprint",
+            Action::Jump => "jump",
+            Action::Crouch => "crouch",
+        }
+    }
+
+    fn default_key(&self) -> KeyCode {
+        match self {
+            Action::Forward => KeyCode::W,
+            Action::Backward => KeyCode::S,
+            Action::StrafeLeft => KeyCode::A,
+            Action::StrafeRight => KeyCode::D,
+            Action::TurnLeft => KeyCode::Left,
+            Action::TurnRight => KeyCode::Right,
+            Action::LookUp => KeyCode::Up,
+            Action::LookDown => KeyCode::Down,
+            Action::Sprint => KeyCode::LeftShift,
+            Action::Jump => KeyCode::Space,
+            Action::Crouch => KeyCode::LeftControl,
+        }
+    }
+}
+
+/// holds the key bound to each action along with the mouse settings
+pub struct Controls {
+    keys: Vec<(Action, KeyCode)>,
+    pub mouse_sensitivity: f32,
+    pub invert_mouse_x: bool,
+    pub invert_mouse_y: bool,
+}
+
+impl Default for Controls {
+    fn default() -> Self {
+        Self {
+            keys: Action::ALL.iter().map(|a| (*a, a.default_key())).collect(),
+            mouse_sensitivity: 0.003,
+            invert_mouse_x: false,
+            invert_mouse_y: false,
+        }
+    }
+}
+
+impl Controls {
+    /// loads the controls from the given file, any missing
+    /// or unreadable entries fall back to their defaults
+    pub fn load(path: &str) -> Self {
+        let mut controls = Self::default();
+
+        let config: Value = match fs::read_to_string(path).ok().and_then(|s| serde_json::from_str(&s).ok()) {
+            Some(v) => v,
+            None => return controls,
+        };
+
+        for action in Action::ALL {
+            if let Some(key) = config["bindings"][action.name()].as_str().and_then(key_from_name) {
+                controls.bind(action, key);
+            }
+        }
+
+        if let Some(v) = config["mouse_sensitivity"].as_f64() {
+            controls.mouse_sensitivity = v as f32;
+        }
+        if let Some(v) = config["invert_mouse_x"].as_bool() {
+            controls.invert_mouse_x = v;
+        }
+        if let Some(v) = config["invert_mouse_y"].as_bool() {
+            controls.invert_mouse_y = v;
+        }
+
+        controls
+    }
+
+    /// writes the current controls out to the given file
+    pub fn save(&self, path: &str) -> std::io::Result<()> {
+        let mut bindings = JsonMap::new();
+        for (action, key) in &self.keys {
+            bindings.insert(action.name().to_owned(), Value::String(format!("{:?}", key)));
+        }
+
+        let config = json!({
+            "bindings": bindings,
+            "mouse_sensitivity": self.mouse_sensitivity,
+            "invert_mouse_x": self.invert_mouse_x,
+            "invert_mouse_y": self.invert_mouse_y,
+        });
+
+        fs::write(path, serde_json::to_string_pretty(&config).unwrap())
+    }
+
+    pub fn key(&self, action: Action) -> KeyCode {
+        self.keys.iter().find(|(a, _)| *a == action).unwrap().1
+    }
+
+    pub fn bind(&mut self, action: Action, key: KeyCode) {
+        if let Some(binding) = self.keys.iter_mut().find(|(a, _)| *a == action) {
+            binding.1 = key;
+        }
+    }
+
+    pub fn is_down(&self, action: Action) -> bool {
+        is_key_down(self.key(action))
+    }
+
+    /// returns how much the view should turn this frame from the mouse movement
+    pub fn mouse_turn(&self, mouse_dx: f32) -> f32 {
+        let turn = mouse_dx * self.mouse_sensitivity;
+        if self.invert_mouse_x {
+            -turn
+        } else {
+            turn
+        }
+    }
+
+    /// returns how much the view should pitch up this frame from the mouse movement
+    pub fn mouse_pitch(&self, mouse_dy: f32) -> f32 {
+        let pitch = -mouse_dy * self.mouse_sensitivity;
+        if self.invert_mouse_y {
+            -pitch
+        } else {
+            pitch
+        }
+    }
+}
+
+/// in game menu for remapping the controls
+#[derive(Default)]
+pub struct ControlsMenu {
+    pub open: bool,
+    selected: usize,
+    waiting_for_key: bool,
+}
+
+impl ControlsMenu {
+    /// returns true while the menu is waiting for a key to bind
+    pub fn is_rebinding(&self) -> bool {
+        self.waiting_for_key
+    }
+
+    /// handles the menu input for this frame, returns true if
+    /// the controls were changed and should be saved
+    pub fn update(&mut self, controls: &mut Controls) -> bool {
+        if self.waiting_for_key {
+            if let Some(key) = get_last_key_pressed() {
+                self.waiting_for_key = false;
+                if key != KeyCode::Escape {
+                    controls.bind(Action::ALL[self.selected], key);
+                    return true;
+                }
+            }
+            return false;
+        }
+
+        let mut changed = false;
+        if is_key_pressed(KeyCode::Up) {
+            self.selected = (self.selected + Action::ALL.len() - 1) % Action::ALL.len();
+        }
+        if is_key_pressed(KeyCode::Down) {
+            self.selected = (self.selected + 1) % Action::ALL.len();
+        }
+        if is_key_pressed(KeyCode::Enter) {
+            self.waiting_for_key = true;
+        }
+        if is_key_pressed(KeyCode::I) {
+            controls.invert_mouse_x = !controls.invert_mouse_x;
+            changed = true;
+        }
+        if is_key_pressed(KeyCode::O) {
+            controls.invert_mouse_y = !controls.invert_mouse_y;
+            changed = true;
+        }
+        if is_key_pressed(KeyCode::Minus) {
+            controls.mouse_sensitivity = (controls.mouse_sensitivity - 0.0005).max(0.0005);
+            changed = true;
+        }
+        if is_key_pressed(KeyCode::Equal) {
+            controls.mouse_sensitivity += 0.0005;
+            changed = true;
+        }
+
+        changed
+    }
+
+    pub fn draw(&self, controls: &Controls) {
+        draw_rectangle(0., 0., screen_width(), screen_height(), Color::new(0., 0., 0., 0.7));
+
+        let mut y = 60.;
+        draw_text("CONTROLS", 40., y, 40., WHITE);
+        y += 20.;
+
+        for (i, action) in Action::ALL.iter().enumerate() {
+            y += 30.;
+            let key = if self.waiting_for_key && i == self.selected {
+                "press a key...".to_owned()
+            } else {
+                format!("{:?}", controls.key(*action))
+            };
+            let color = if i == self.selected { YELLOW } else { WHITE };
+            draw_text(format!("{:<14}{}", action.name(), key).as_str(), 40., y, 30., color);
+        }
+
+        y += 50.;
+        draw_text(format!("mouse sensitivity: {:.4} (-/=)", controls.mouse_sensitivity).as_str(), 40., y, 30., WHITE);
+        y += 30.;
+        draw_text(format!("invert mouse x: {} (I)", controls.invert_mouse_x).as_str(), 40., y, 30., WHITE);
+        y += 30.;
+        draw_text(format!("invert mouse y: {} (O)", controls.invert_mouse_y).as_str(), 40., y, 30., WHITE);
+        y += 50.;
+        draw_text("up/down: select, enter: rebind, tab: close", 40., y, 30., GRAY);
+    }
+}
+
+/// finds the key code with the given name, names
+/// match the debug output of the key codes
+pub fn key_from_name(name: &str) -> Option<KeyCode> {
+    ALL_KEYS.iter().copied().find(|key| format!("{:?}", key) == name)
+}
+
+const ALL_KEYS: [KeyCode; 121] = [
+    KeyCode::Space, KeyCode::Apostrophe, KeyCode::Comma, KeyCode::Minus, KeyCode::Period, KeyCode::Slash,
+    KeyCode::Key0, KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4,
+    KeyCode::Key5, KeyCode::Key6, KeyCode::Key7, KeyCode::Key8, KeyCode::Key9,
+    KeyCode::Semicolon, KeyCode::Equal,
+    KeyCode::A, KeyCode::B, KeyCode::C, KeyCode::D, KeyCode::E, KeyCode::F, KeyCode::G,
+    KeyCode::H, KeyCode::I, KeyCode::J, KeyCode::K, KeyCode::L, KeyCode::M, KeyCode::N,
+    KeyCode::O, KeyCode::P, KeyCode::Q, KeyCode::R, KeyCode::S, KeyCode::T, KeyCode::U,
+    KeyCode::V, KeyCode::W, KeyCode::X, KeyCode::Y, KeyCode::Z,
+    KeyCode::LeftBracket, KeyCode::Backslash, KeyCode::RightBracket, KeyCode::GraveAccent,
+    KeyCode::World1, KeyCode::World2,
+    KeyCode::Escape, KeyCode::Enter, KeyCode::Tab, KeyCode::Backspace, KeyCode::Insert, KeyCode::Delete,
+    KeyCode::Right, KeyCode::Left, KeyCode::Down, KeyCode::Up,
+    KeyCode::PageUp, KeyCode::PageDown, KeyCode::Home, KeyCode::End,
+    KeyCode::CapsLock, KeyCode::ScrollLock, KeyCode::NumLock, KeyCode::PrintScreen, KeyCode::Pause,
+    KeyCode::F1, KeyCode::F2, KeyCode::F3, KeyCode::F4, KeyCode::F5, KeyCode::F6, KeyCode::F7,
+    KeyCode::F8, KeyCode::F9, KeyCode::F10, KeyCode::F11, KeyCode::F12, KeyCode::F13, KeyCode::F14,
+    KeyCode::F15, KeyCode::F16, KeyCode::F17, KeyCode::F18, KeyCode::F19, KeyCode::F20, KeyCode::F21,
+    KeyCode::F22, KeyCode::F23, KeyCode::F24, KeyCode::F25,
+    KeyCode::Kp0, KeyCode::Kp1, KeyCode::Kp2, KeyCode::Kp3, KeyCode::Kp4,
+    KeyCode::Kp5, KeyCode::Kp6, KeyCode::Kp7, KeyCode::Kp8, KeyCode::Kp9,
+    KeyCode::KpDecimal, KeyCode::KpDivide, KeyCode::KpMultiply, KeyCode::KpSubtract,
+    KeyCode::KpAdd, KeyCode::KpEnter, KeyCode::KpEqual,
+    KeyCode::LeftShift, KeyCode::LeftControl, KeyCode::LeftAlt, KeyCode::LeftSuper,
+    KeyCode::RightShift, KeyCode::RightControl, KeyCode::RightAlt, KeyCode::RightSuper,
+    KeyCode::Menu, KeyCode::Unknown,
+];
@@ -0,0 +1,320 @@
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use crate::{cast_multi, Map, PortalTransform, RayCastEngine, RayData, SpanKind};
+
+/// a number with 16 bits after the point stored in an i32, so it can be from about -32768 to 32768
+/// in steps of 1/65536. all of the maths on it is done with integers so it gives exactly the same
+/// results on every machine, which floats dont promise once things like `sin` get involved.
+/// adding, subtracting and multiplying wrap around when the result is out of range and dividing saturates,
+/// even when dividing by zero
+#[derive(Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Fixed(pub i32);
+
+/// how many bits are after the point
+const FRAC_BITS: u32 = 16;
+
+/// how many bits are after the point while working out sines and cosines
+const CORDIC_BITS: u32 = 30;
+
+/// `atan(2^-i)` with 30 bits after the point, the angles the cordic rotations turn by
+const CORDIC_ANGLES: [i64; 30] = [
+    843314857, 497837829, 263043837, 133525159, 67021687, 33543516, 16775851, 8388437, 4194283, 2097149,
+    1048576, 524288, 262144, 131072, 65536, 32768, 16384, 8192, 4096, 2048,
+    1024, 512, 256, 128, 64, 32, 16, 8, 4, 2,
+];
+
+/// how much shorter a vector ends up after all of the cordic rotations, with 30 bits after the point
+const CORDIC_GAIN: i64 = 652032874;
+
+/// pi with 30 bits after the point
+const CORDIC_PI: i64 = 3373259426;
+
+impl Fixed {
+    pub const ZERO: Fixed = Fixed(0);
+    pub const ONE: Fixed = Fixed(1 << FRAC_BITS);
+    pub const PI: Fixed = Fixed(205887);
+    pub const TAU: Fixed = Fixed(411775);
+
+    /// the fixed point number for an integer, only integers from -32768 to 32767 fit and the rest wrap around
+    pub fn from_int(value: i32) -> Self {
+        Self(value << FRAC_BITS)
+    }
+
+    /// the nearest fixed point number to a float, saturating when it is out of range
+    pub fn from_f32(value: f32) -> Self {
+        Self((value * Self::ONE.0 as f32).round() as i32)
+    }
+
+    /// the nearest float to this number, which is always the same on every machine
+    pub fn to_f32(self) -> f32 {
+        self.0 as f32 / Self::ONE.0 as f32
+    }
+
+    /// the biggest integer that isnt bigger than this number
+    pub fn floor(self) -> i32 {
+        self.0 >> FRAC_BITS
+    }
+
+    /// the part after the point, always from 0 up to but not including 1
+    pub fn fract(self) -> Self {
+        Self(self.0 & (Self::ONE.0 - 1))
+    }
+
+    pub fn abs(self) -> Self {
+        Self(self.0.wrapping_abs())
+    }
+
+    /// the sine and cosine of an angle in radians, worked out with cordic rotations
+    /// so they only use shifts and adds. they are within about 1/65536 of the real values
+    pub fn sin_cos(self) -> (Fixed, Fixed) {
+        // moves the angle between -pi and pi
+        let mut angle = self.0.rem_euclid(Self::TAU.0);
+        if angle > Self::PI.0 {
+            angle -= Self::TAU.0;
+        }
+        let mut angle = (angle as i64) << (CORDIC_BITS - FRAC_BITS);
+
+        // the rotations only reach angles between -pi/2 and pi/2, the rest are turned half way around
+        let mut sign = 1;
+        if angle > CORDIC_PI / 2 {
+            angle -= CORDIC_PI;
+            sign = -1;
+        } else if angle < -CORDIC_PI / 2 {
+            angle += CORDIC_PI;
+            sign = -1;
+        }
+
+        // rotates (1, 0) by smaller and smaller steps until it has turned by the angle
+        let (mut x, mut y) = (CORDIC_GAIN, 0);
+        for (i, step) in CORDIC_ANGLES.iter().enumerate() {
+            let (dx, dy) = (y >> i, x >> i);
+            if angle >= 0 {
+                (x, y) = (x - dx, y + dy);
+                angle -= step;
+            } else {
+                (x, y) = (x + dx, y - dy);
+                angle += step;
+            }
+        }
+
+        let round = |value: i64| Fixed((sign * ((value + (1 << (CORDIC_BITS - FRAC_BITS - 1))) >> (CORDIC_BITS - FRAC_BITS))) as i32);
+        (round(y), round(x))
+    }
+
+    /// this number with 32 bits after the point
+    fn wide(self) -> i64 {
+        (self.0 as i64) << FRAC_BITS
+    }
+
+    /// `a / b` rounded down, for numbers with 32 bits after the point divided by ones with 16. saturates when
+    /// out of range, which dividing by zero always is unless `a` is zero too, then the result is zero
+    fn div_wide(a: i64, b: i64) -> Self {
+        if b == 0 {
+            return Self(match a.signum() {
+                1 => i32::MAX,
+                -1 => i32::MIN,
+                _ => 0,
+            });
+        }
+        Self(a.div_euclid(b).clamp(i32::MIN as i64, i32::MAX as i64) as i32)
+    }
+}
+
+impl Add for Fixed {
+    type Output = Fixed;
+
+    fn add(self, other: Fixed) -> Fixed {
+        Fixed(self.0.wrapping_add(other.0))
+    }
+}
+
+impl Sub for Fixed {
+    type Output = Fixed;
+
+    fn sub(self, other: Fixed) -> Fixed {
+        Fixed(self.0.wrapping_sub(other.0))
+    }
+}
+
+impl Neg for Fixed {
+    type Output = Fixed;
+
+    fn neg(self) -> Fixed {
+        Fixed(self.0.wrapping_neg())
+    }
+}
+
+/// rounds down to the nearest 1/65536
+impl Mul for Fixed {
+    type Output = Fixed;
+
+    fn mul(self, other: Fixed) -> Fixed {
+        Fixed(((self.0 as i64 * other.0 as i64) >> FRAC_BITS) as i32)
+    }
+}
+
+/// rounds down to the nearest 1/65536, dividing by zero gives the biggest or smallest number or zero for 0 / 0
+impl Div for Fixed {
+    type Output = Fixed;
+
+    fn div(self, other: Fixed) -> Fixed {
+        Fixed::div_wide(self.wide(), other.0 as i64)
+    }
+}
+
+/// steps a ray through the map one cell at a time with only integers. instead of keeping the length
+/// of the ray at the next edge along each axis it keeps how far away those edges are along the axes,
+/// and compares them by multiplying across so nothing is rounded until a length is needed
+struct FixedWalker {
+    cell: (i32, i32),
+    step: (i32, i32),
+
+    /// where the ray started and the direction it goes in, in world space
+    pos: (Fixed, Fixed),
+    dir: (Fixed, Fixed),
+
+    cell_size: (Fixed, Fixed),
+    origin: (Fixed, Fixed),
+}
+
+impl FixedWalker {
+    fn new(pos: (Fixed, Fixed), dir: (Fixed, Fixed), cell_size: (Fixed, Fixed), origin: (Fixed, Fixed)) -> Self {
+        // finds the cell by dividing the integers so points right on an edge always go in the cell after it
+        let cell = (
+            ((pos.0 .0 as i64 - origin.0 .0 as i64).div_euclid(cell_size.0 .0 as i64)) as i32,
+            ((pos.1 .0 as i64 - origin.1 .0 as i64).div_euclid(cell_size.1 .0 as i64)) as i32,
+        );
+        let step = (if dir.0 .0 < 0 { -1 } else { 1 }, if dir.1 .0 < 0 { -1 } else { 1 });
+
+        Self { cell, step, pos, dir, cell_size, origin }
+    }
+
+    /// how far along each axis the ray has to go to reach the next edge it crosses along that axis
+    fn edge_distances(&self) -> (i64, i64) {
+        let distance = |cell: i32, step: i32, pos: Fixed, cell_size: Fixed, origin: Fixed| {
+            let edge = origin.0 as i64 + (cell + (step > 0) as i32) as i64 * cell_size.0 as i64;
+            (edge - pos.0 as i64).abs()
+        };
+        (
+            distance(self.cell.0, self.step.0, self.pos.0, self.cell_size.0, self.origin.0),
+            distance(self.cell.1, self.step.1, self.pos.1, self.cell_size.1, self.origin.1),
+        )
+    }
+
+    /// the length of the ray when it leaves the current cell and if it leaves through a vertical edge,
+    /// vertical edges have to be strictly closer to be crossed first the same as `DdaWalker::step`
+    fn next_edge(&self) -> (Fixed, bool) {
+        let distances = self.edge_distances();
+        let dir = (self.dir.0 .0.abs() as i64, self.dir.1 .0.abs() as i64);
+
+        // distance.0 / dir.0 < distance.1 / dir.1 without dividing, an axis the ray doesnt move along never wins
+        if distances.0 * dir.1 < distances.1 * dir.0 {
+            (Fixed::div_wide(distances.0 << FRAC_BITS, dir.0), true)
+        } else {
+            (Fixed::div_wide(distances.1 << FRAC_BITS, dir.1), false)
+        }
+    }
+
+    /// walks into the next cell along the ray, returns the length of the ray
+    /// at the edge it crossed and if that edge was a vertical one
+    fn step(&mut self) -> (Fixed, bool) {
+        let (distance, vertical) = self.next_edge();
+        if vertical {
+            self.cell.0 += self.step.0;
+        } else {
+            self.cell.1 += self.step.1;
+        }
+        (distance, vertical)
+    }
+
+    /// how far along the wall a ray that crossed an edge at `distance` is from 0 to 1, the same as `edge_u`
+    fn edge_u(&self, distance: Fixed, collided_vertical: bool) -> Fixed {
+        let (pos, dir, cell_size, origin) = if collided_vertical {
+            (self.pos.1, self.dir.1, self.cell_size.1, self.origin.1)
+        } else {
+            (self.pos.0, self.dir.0, self.cell_size.0, self.origin.0)
+        };
+
+        // where the ray crossed the edge in grid space, with the multiplication kept exact
+        let along = ((pos.0 as i64 - origin.0 as i64) << FRAC_BITS) + dir.0 as i64 * distance.0 as i64;
+        Fixed::div_wide(along, cell_size.0 as i64).fract()
+    }
+}
+
+impl<T: Map + std::marker::Sync> RayCastEngine<T> {
+    /// casts a ray the same as `cast_ray` but with fixed point numbers, so the same inputs give bit for
+    /// bit the same results on every machine, for things like lockstep multiplayer and replays. every
+    /// number in the results comes from a fixed point one. `cell_size` and `origin` are changed to fixed point too,
+    /// cell sizes smaller than 1/65536 are rounded up to it since cells cant be empty.
+    /// only plain grids of full walls are supported. `get_shape` is ignored so every wall fills its whole
+    /// cell, `get_portal` is ignored so rays go into portal cells like empty ones, `is_mirror` is ignored
+    /// so mirrors stop rays like other walls and the distance field isnt used. on maps with shaped walls,
+    /// portals or mirrors the results wont match `cast_ray`
+    pub fn cast_ray_fixed(&self, pos: (Fixed, Fixed), angle: Fixed, max_distance: Fixed) -> RayData {
+        let (sin, cos) = angle.sin_cos();
+        let cell_size = (Fixed(Fixed::from_f32(self.cell_size.0).0.max(1)), Fixed(Fixed::from_f32(self.cell_size.1).0.max(1)));
+        let origin = (Fixed::from_f32(self.origin.0), Fixed::from_f32(self.origin.1));
+        let mut walker = FixedWalker::new(pos, (cos, sin), cell_size, origin);
+
+        // initialize info needed for the casting process
+        let mut hit = None;
+        let mut distance = Fixed::ZERO;
+        let mut collided_vertical = false;
+
+        while distance < max_distance {
+            (distance, collided_vertical) = walker.step();
+
+            // checks if the current cell in the map is a wall
+            match self.cell_at(walker.cell) {
+                Some(current_cell) => {
+                    if current_cell > 0 {
+                        hit = Some(current_cell);
+                        break;
+                    }
+                }
+                // we are outside the map, break early
+                None => break,
+            }
+        }
+
+        let normal = if collided_vertical {
+            (-walker.step.0 as f32, 0.)
+        } else {
+            (0., -walker.step.1 as f32)
+        };
+
+        let mut ray = RayData {
+            ray_length: distance.to_f32(),
+            hit_val: hit,
+            ray_angle: angle.to_f32(),
+            ray_position: (pos.0.to_f32(), pos.1.to_f32()),
+            ray_direction: (cos.to_f32(), sin.to_f32()),
+            collided_vertical,
+            wall_span: (0., 0.),
+            exit_length: walker.next_edge().0.to_f32(),
+            hit_cell: walker.cell,
+            span_kind: SpanKind::Wall,
+            view_slopes: (f32::NEG_INFINITY, f32::INFINITY),
+            layer: 0,
+            texture_u: walker.edge_u(distance, collided_vertical).to_f32(),
+            normal,
+            transform: PortalTransform::default(),
+            local_origin: (pos.0.to_f32(), pos.1.to_f32()),
+            local_direction: (cos.to_f32(), sin.to_f32()),
+            bounces: 0,
+        };
+
+        if hit.is_some() {
+            let (x, y) = (walker.cell.0 as usize, walker.cell.1 as usize);
+            let floor = self.map.get_floor_height(x, y);
+            ray.wall_span = (floor, floor + self.map.get_height(x, y));
+        }
+
+        ray
+    }
+
+    /// casts every angle with `cast_ray_fixed` using rayon, along with the index of their angle
+    pub fn cast_rays_fixed_multi(&self, pos: (Fixed, Fixed), angles: Vec<Fixed>, max_distance: Fixed) -> Vec<(usize, RayData)> {
+        cast_multi(&angles, |angle| self.cast_ray_fixed(pos, *angle, max_distance))
+    }
+}
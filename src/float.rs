@@ -0,0 +1,121 @@
+use std::fmt::Debug;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+/// the float type an engine casts rays with, f32 is quicker and f64 stays
+/// precise much farther away from the origin of the map on large maps.
+/// most casts only work with f32, see `RayCastEngine::with_precision`
+pub trait Float:
+    Copy
+    + Default
+    + PartialOrd
+    + Debug
+    + Send
+    + Sync
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+{
+    const ZERO: Self;
+    const ONE: Self;
+    const INFINITY: Self;
+    const NEG_INFINITY: Self;
+    const FRAC_PI_2: Self;
+    const TAU: Self;
+
+    fn from_f32(value: f32) -> Self;
+    fn from_i32(value: i32) -> Self;
+    fn from_u32(value: u32) -> Self;
+
+    /// rounds towards zero the same as `as` does, saturating when out of range and 0 for NaN
+    fn to_i32(self) -> i32;
+    fn to_u32(self) -> u32;
+
+    fn sqrt(self) -> Self;
+    fn floor(self) -> Self;
+    fn abs(self) -> Self;
+    fn signum(self) -> Self;
+    fn min(self, other: Self) -> Self;
+    fn max(self, other: Self) -> Self;
+    fn sin(self) -> Self;
+    fn cos(self) -> Self;
+    fn atan2(self, other: Self) -> Self;
+    fn rem_euclid(self, other: Self) -> Self;
+}
+
+macro_rules! impl_float {
+    ($float:ident) => {
+        impl Float for $float {
+            const ZERO: Self = 0.;
+            const ONE: Self = 1.;
+            const INFINITY: Self = $float::INFINITY;
+            const NEG_INFINITY: Self = $float::NEG_INFINITY;
+            const FRAC_PI_2: Self = std::$float::consts::FRAC_PI_2;
+            const TAU: Self = std::$float::consts::TAU;
+
+            fn from_f32(value: f32) -> Self {
+                value as $float
+            }
+
+            fn from_i32(value: i32) -> Self {
+                value as $float
+            }
+
+            fn from_u32(value: u32) -> Self {
+                value as $float
+            }
+
+            fn to_i32(self) -> i32 {
+                self as i32
+            }
+
+            fn to_u32(self) -> u32 {
+                self as u32
+            }
+
+            fn sqrt(self) -> Self {
+                $float::sqrt(self)
+            }
+
+            fn floor(self) -> Self {
+                $float::floor(self)
+            }
+
+            fn abs(self) -> Self {
+                $float::abs(self)
+            }
+
+            fn signum(self) -> Self {
+                $float::signum(self)
+            }
+
+            fn min(self, other: Self) -> Self {
+                $float::min(self, other)
+            }
+
+            fn max(self, other: Self) -> Self {
+                $float::max(self, other)
+            }
+
+            fn sin(self) -> Self {
+                $float::sin(self)
+            }
+
+            fn cos(self) -> Self {
+                $float::cos(self)
+            }
+
+            fn atan2(self, other: Self) -> Self {
+                $float::atan2(self, other)
+            }
+
+            fn rem_euclid(self, other: Self) -> Self {
+                $float::rem_euclid(self, other)
+            }
+        }
+    };
+}
+
+impl_float!(f32);
+impl_float!(f64);
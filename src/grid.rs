@@ -0,0 +1,109 @@
+use crate::{Float, RayCastEngine, RayData, RaySegment};
+
+/// a ray moved from world space into the space the map is walked in, where each cell is
+/// 1 unit wide and the top left corner of the map is at (0, 0)
+pub(crate) struct GridRay<F = f32> {
+    pub pos: (F, F),
+
+    /// a unit vector in grid space
+    pub dir: (F, F),
+
+    /// how many units the ray goes in grid space for every unit it goes in world space
+    pub scale: F,
+
+    world_pos: (F, F),
+    world_angle: F,
+    cell_size: (F, F),
+    origin: (F, F),
+}
+
+impl<F: Float> GridRay<F> {
+    /// changes a length in world space to one in grid space
+    pub fn length(&self, world_length: F) -> F {
+        world_length * self.scale
+    }
+
+    /// moves the results of casting the ray in grid space back into world space, cells,
+    /// texture coordinates and portal transforms stay the same since they are per cell
+    pub fn to_world(&self, ray: RayData<F>) -> RayData<F> {
+        // normals get squashed the opposite way to the cells
+        let normal = (ray.normal.0 / self.cell_size.0, ray.normal.1 / self.cell_size.1);
+        let normal_length = (normal.0 * normal.0 + normal.1 * normal.1).sqrt();
+
+        RayData {
+            ray_length: ray.ray_length / self.scale,
+            exit_length: ray.exit_length / self.scale,
+            ray_angle: self.world_angle,
+            ray_position: self.world_pos,
+            ray_direction: (self.world_angle.cos(), self.world_angle.sin()),
+            view_slopes: (ray.view_slopes.0 * self.scale, ray.view_slopes.1 * self.scale),
+            normal: if self.cell_size != (F::ONE, F::ONE) && normal_length > F::ZERO {
+                (normal.0 / normal_length, normal.1 / normal_length)
+            } else {
+                ray.normal
+            },
+            local_origin: self.point(ray.local_origin),
+            local_direction: (
+                ray.local_direction.0 * self.cell_size.0 * self.scale,
+                ray.local_direction.1 * self.cell_size.1 * self.scale,
+            ),
+            ..ray
+        }
+    }
+
+    /// moves a segment of the ray from grid space back into world space
+    pub fn segment_to_world(&self, segment: RaySegment<F>) -> RaySegment<F> {
+        RaySegment {
+            start: self.point(segment.start),
+            end: self.point(segment.end),
+            start_length: segment.start_length / self.scale,
+            end_length: segment.end_length / self.scale,
+        }
+    }
+
+    fn point(&self, pos: (F, F)) -> (F, F) {
+        (self.origin.0 + pos.0 * self.cell_size.0, self.origin.1 + pos.1 * self.cell_size.1)
+    }
+}
+
+impl<T, F: Float> RayCastEngine<T, F> {
+    /// changes a point in world space to where it is on the map, where each cell is 1 unit wide
+    pub fn to_grid(&self, pos: (F, F)) -> (F, F) {
+        ((pos.0 - self.origin.0) / self.cell_size.0, (pos.1 - self.origin.1) / self.cell_size.1)
+    }
+
+    /// changes a point on the map back into world space
+    pub fn to_world(&self, pos: (F, F)) -> (F, F) {
+        (self.origin.0 + pos.0 * self.cell_size.0, self.origin.1 + pos.1 * self.cell_size.1)
+    }
+
+    /// the cell a point in world space is in
+    pub fn cell_at_point(&self, pos: (F, F)) -> (i32, i32) {
+        let grid = self.to_grid(pos);
+        (grid.0.floor().to_i32(), grid.1.floor().to_i32())
+    }
+
+    /// moves a ray starting at `pos` going at `angle` in world space into grid space
+    pub(crate) fn grid_ray(&self, pos: (F, F), angle: F) -> GridRay<F> {
+        let world_dir = (angle.cos(), angle.sin());
+
+        // square unit cells leave the direction alone so the default grid gives exact results
+        let (dir, scale) = if self.cell_size == (F::ONE, F::ONE) {
+            (world_dir, F::ONE)
+        } else {
+            let dir = (world_dir.0 / self.cell_size.0, world_dir.1 / self.cell_size.1);
+            let scale = (dir.0 * dir.0 + dir.1 * dir.1).sqrt();
+            ((dir.0 / scale, dir.1 / scale), scale)
+        };
+
+        GridRay {
+            pos: self.to_grid(pos),
+            dir,
+            scale,
+            world_pos: pos,
+            world_angle: angle,
+            cell_size: self.cell_size,
+            origin: self.origin,
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use crate::{cast_multi, RayData};
+
+/// trait to use to make your own custom structure for hex maps, cells are found with
+/// axial coordinates where q goes along the rows and r goes down them
+pub trait HexMap {
+    /// the value in the given cell, or None if the cell is outside of the map
+    fn get_cell(&self, q: i32, r: i32) -> Option<u32>;
+}
+
+/// which way the hexagons in a hex map point
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HexOrientation {
+    /// rows of hexagons with a corner at the top, q goes along the x axis
+    #[default]
+    PointyTop,
+
+    /// columns of hexagons with a flat edge at the top, q goes along the x axis in a zigzag
+    FlatTop,
+}
+
+const SQRT_3: f32 = 1.732_050_8;
+
+impl HexOrientation {
+    /// the center of a cell, hexagons are 1 unit from their center to each corner
+    pub fn center(&self, cell: (i32, i32)) -> (f32, f32) {
+        let (q, r) = (cell.0 as f32, cell.1 as f32);
+        match self {
+            HexOrientation::PointyTop => (SQRT_3 * (q + r / 2.), 1.5 * r),
+            HexOrientation::FlatTop => (1.5 * q, SQRT_3 * (r + q / 2.)),
+        }
+    }
+
+    /// the cell a point is in
+    pub fn cell_at(&self, pos: (f32, f32)) -> (i32, i32) {
+        let (q, r) = match self {
+            HexOrientation::PointyTop => (SQRT_3 / 3. * pos.0 - pos.1 / 3., 2. / 3. * pos.1),
+            HexOrientation::FlatTop => (2. / 3. * pos.0, SQRT_3 / 3. * pos.1 - pos.0 / 3.),
+        };
+
+        // rounds in cube coordinates, fixing whichever one moved the most so they still add up to 0
+        let s = -q - r;
+        let (mut rq, mut rr, rs) = (q.round(), r.round(), s.round());
+        let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+        if dq > dr && dq > ds {
+            rq = -rr - rs;
+        } else if dr > ds {
+            rr = -rq - rs;
+        }
+        (rq as i32, rr as i32)
+    }
+
+    /// the three neighbours of a cell that are each across a different pair of opposite edges
+    fn axes(&self) -> [(i32, i32); 3] {
+        match self {
+            HexOrientation::PointyTop => [(1, 0), (0, 1), (-1, 1)],
+            HexOrientation::FlatTop => [(0, 1), (1, 0), (1, -1)],
+        }
+    }
+}
+
+/// ray cast engine to hold a hex map and allow the user to cast rays from any point in the map
+pub struct HexRayCastEngine<T: HexMap> {
+    pub map: T,
+    pub orientation: HexOrientation,
+}
+
+impl<T: HexMap + std::marker::Sync> HexRayCastEngine<T> {
+    /// creates a new engine with the provided map, cells outside
+    /// of the map are found by the map returning None for them
+    pub fn new(map: T, orientation: HexOrientation) -> Self {
+        Self { map, orientation }
+    }
+
+    /// takes a vector of angles for rays to be casted at and
+    /// uses rayon to cast them all with multithreading, the results
+    /// are returned along with their angles index
+    pub fn cast_rays_multi(&self, pos: (f32, f32), angles: Vec<f32>, max_distance: f32) -> Vec<(usize, RayData)> {
+        cast_multi(&angles, |angle| self.cast_ray(pos, *angle, max_distance))
+    }
+
+    /// casts a single ray from the given position with the given angle, stepping from hexagon to
+    /// hexagon through whichever edge the ray leaves by. the hit cell is in axial coordinates
+    /// and `collided_vertical` is if the edge hit is closer to vertical than horizontal
+    pub fn cast_ray(&self, pos: (f32, f32), angle: f32, max_distance: f32) -> RayData {
+        // makes a normalized vector with the provided angle
+        let ray_dir = (f32::cos(angle), f32::sin(angle));
+
+        let mut walker = HexWalker::new(self.orientation, pos, ray_dir);
+
+        let mut ray = RayData {
+            ray_angle: angle,
+            ray_position: pos,
+            ray_direction: ray_dir,
+            hit_cell: walker.cell,
+            view_slopes: (f32::NEG_INFINITY, f32::INFINITY),
+            local_origin: pos,
+            local_direction: ray_dir,
+            ..Default::default()
+        };
+
+        let mut distance = 0_f32;
+        while distance < max_distance {
+            let normal;
+            (distance, normal) = walker.step();
+
+            ray.ray_length = distance;
+            ray.hit_cell = walker.cell;
+            ray.normal = normal;
+            ray.collided_vertical = normal.0.abs() > normal.1.abs();
+            ray.exit_length = walker.exit().0;
+
+            match self.map.get_cell(walker.cell.0, walker.cell.1) {
+                Some(val) if val > 0 => {
+                    ray.hit_val = Some(val);
+                    ray.wall_span = (0., 1.);
+                    ray.texture_u = walker.edge_u(distance, normal);
+                    break;
+                }
+                Some(_) => {}
+                // we are outside the map, break early
+                None => break,
+            }
+        }
+
+        ray
+    }
+}
+
+/// steps a ray through a hex map one cell at a time
+struct HexWalker {
+    orientation: HexOrientation,
+
+    /// the cell the ray is currently in
+    cell: (i32, i32),
+
+    origin: (f32, f32),
+    dir: (f32, f32),
+}
+
+impl HexWalker {
+    fn new(orientation: HexOrientation, pos: (f32, f32), ray_dir: (f32, f32)) -> Self {
+        Self {
+            orientation,
+            cell: orientation.cell_at(pos),
+            origin: pos,
+            dir: ray_dir,
+        }
+    }
+
+    /// the length of the ray when it leaves the current cell along with the neighbour it goes into
+    /// and the normal of the edge it crosses, pointing out of the current cell
+    fn exit(&self) -> (f32, (i32, i32), (f32, f32)) {
+        let center = self.orientation.center(self.cell);
+        let to_origin = (self.origin.0 - center.0, self.origin.1 - center.1);
+
+        // each pair of opposite edges is half the distance between neighbouring centers from the center
+        let apothem = SQRT_3 / 2.;
+
+        let mut exit = (f32::INFINITY, self.cell, (0., 0.));
+        for axis in self.orientation.axes() {
+            let neighbour = self.orientation.center((self.cell.0 + axis.0, self.cell.1 + axis.1));
+            let mut normal = ((neighbour.0 - center.0) / SQRT_3, (neighbour.1 - center.1) / SQRT_3);
+            let mut step = axis;
+
+            // the ray can only leave through the edge of the pair it is heading towards
+            let speed = self.dir.0 * normal.0 + self.dir.1 * normal.1;
+            if speed == 0. {
+                continue;
+            }
+            if speed < 0. {
+                normal = (-normal.0, -normal.1);
+                step = (-step.0, -step.1);
+            }
+
+            let length = (apothem - (to_origin.0 * normal.0 + to_origin.1 * normal.1)) / speed.abs();
+            if length < exit.0 {
+                exit = (length, (self.cell.0 + step.0, self.cell.1 + step.1), normal);
+            }
+        }
+        exit
+    }
+
+    /// walks into the next cell along the ray, returns the length of the ray at the edge
+    /// it crossed and the normal of that edge pointing back towards where the ray came from
+    fn step(&mut self) -> (f32, (f32, f32)) {
+        let (length, next, normal) = self.exit();
+        self.cell = next;
+        (length, (-normal.0, -normal.1))
+    }
+
+    /// how far along the edge of the current cell with the given normal the ray crosses it, from 0 to 1
+    fn edge_u(&self, distance: f32, normal: (f32, f32)) -> f32 {
+        let center = self.orientation.center(self.cell);
+        let hit = (self.origin.0 + self.dir.0 * distance - center.0, self.origin.1 + self.dir.1 * distance - center.1);
+
+        // edges are 1 unit long and centered on the line through the normal
+        let along = (-normal.1, normal.0);
+        (hit.0 * along.0 + hit.1 * along.1 + 0.5).clamp(0., 1.)
+    }
+}
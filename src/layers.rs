@@ -0,0 +1,119 @@
+use crate::{cast_multi, CellShape, DdaWalker, Map, RayCastEngine, RayData};
+
+/// a set of maps covering the same area that are all casted against at once, like one for walls,
+/// one for glass and one for things. maps of different types can be mixed by using `&(dyn Map + Sync)`
+pub struct Layers<T: Map> {
+    /// the layers in order, they should all be the same size. rays go through
+    /// the portals and bounce off the mirrors in the first layer only
+    pub layers: Vec<T>,
+}
+
+impl<T: Map> Layers<T> {
+    pub fn new(layers: Vec<T>) -> Self {
+        Self { layers }
+    }
+}
+
+impl<T: Map + std::marker::Sync> RayCastEngine<Layers<T>> {
+    /// casts a single ray through every layer at once and returns the nearest hit in each layer
+    /// that was hit, ordered from nearest to farthest. `layer` says which layer each hit is in.
+    /// walls in one layer dont block the others, the ray keeps going until every layer has been
+    /// hit or it reaches `max_distance`
+    pub fn cast_ray_layers(&self, pos: (f32, f32), angle: f32, max_distance: f32) -> Vec<RayData> {
+        let grid = self.grid_ray(pos, angle);
+        let max_distance = grid.length(max_distance);
+
+        let layers = &self.map.layers;
+        let mut walker = DdaWalker::new(grid.pos, grid.dir);
+        let mut hits: Vec<Option<RayData>> = vec![None; layers.len()];
+
+        let mut distance = 0_f32;
+        let mut collided_vertical = false;
+        let mut bounces = 0;
+
+        // the ray can hit thin walls in the cell it starts in, but not the edges of full ones
+        let mut start = true;
+
+        while self.in_bounds(walker.cell) {
+            let (x, y) = (walker.cell.0 as usize, walker.cell.1 as usize);
+            let mut span = (distance, walker.exit_length());
+            let mut mirror = None;
+
+            for (i, layer) in layers.iter().enumerate() {
+                if hits[i].is_some() {
+                    continue;
+                }
+
+                let val = match layer.get_cell(x, y) {
+                    Some(val) if val > 0 => val,
+                    _ => continue,
+                };
+                let shape = layer.get_shape(x, y);
+                if start && shape == CellShape::Block {
+                    continue;
+                }
+
+                let hit = match shape.intersect(walker.cell, walker.origin, walker.dir, span, collided_vertical) {
+                    Some(hit) => hit,
+                    None => continue,
+                };
+
+                // the rest of the cell is seen in the mirror so the other layers can only be hit before it
+                if i == 0 && bounces < self.max_bounces && layer.is_mirror(x, y) {
+                    mirror = Some((hit, shape));
+                    span.1 = hit.distance;
+                    continue;
+                }
+
+                hits[i] = Some(RayData {
+                    ray_length: hit.distance,
+                    hit_val: Some(val),
+                    ray_position: grid.pos,
+                    ray_direction: grid.dir,
+                    collided_vertical: hit.collided_vertical,
+                    wall_span: (layer.get_floor_height(x, y), layer.get_floor_height(x, y) + layer.get_height(x, y)),
+                    exit_length: walker.exit_length(),
+                    hit_cell: walker.cell,
+                    view_slopes: (f32::NEG_INFINITY, f32::INFINITY),
+                    layer: i,
+                    texture_u: hit.texture_u,
+                    normal: hit.normal,
+                    transform: walker.transform,
+                    local_origin: walker.origin,
+                    local_direction: walker.dir,
+                    bounces,
+                    ..Default::default()
+                });
+            }
+
+            if let Some((hit, shape)) = mirror {
+                walker.bounce(&hit, shape);
+                distance = hit.distance;
+                bounces += 1;
+            }
+
+            if hits.iter().all(|hit| hit.is_some()) || distance >= max_distance {
+                break;
+            }
+
+            (distance, collided_vertical) = walker.step();
+            collided_vertical = walker.follow_portals(collided_vertical, |cell| {
+                if self.in_bounds(cell) {
+                    layers.first().and_then(|layer| layer.get_portal(cell.0 as usize, cell.1 as usize))
+                } else {
+                    None
+                }
+            });
+            start = false;
+        }
+
+        let mut hits: Vec<RayData> = hits.into_iter().flatten().map(|hit| grid.to_world(hit)).collect();
+        hits.sort_by(|a, b| a.ray_length.total_cmp(&b.ray_length));
+        hits
+    }
+
+    /// same as `cast_rays_multi` but uses `cast_ray_layers` for each ray
+    pub fn cast_rays_multi_layers(&self, pos: (f32, f32), angles: Vec<f32>, max_distance: f32) -> Vec<(usize, Vec<RayData>)> {
+        cast_multi(&angles, |angle| self.cast_ray_layers(pos, *angle, max_distance))
+    }
+}
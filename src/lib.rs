@@ -1,154 +1,612 @@
 use rayon::prelude::*;
+use grid::GridRay;
+use packet::PACKET_SIZE;
+use shapes::{edge_normal, edge_u};
 use std::sync::mpsc;
 
+mod accel;
+mod cone;
+#[cfg(feature = "fixed")]
+mod fixed;
+mod float;
+mod grid;
+mod hex;
+mod layers;
+mod mirrors;
+mod objects;
+mod occupancy;
+mod packet;
+mod portals;
+mod sdf;
+mod sensor;
+mod shapes;
+mod simd;
+mod spatial_hash;
+mod spans;
+mod storeys;
+mod sweep;
+mod voxel;
+
+pub use accel::DistanceField;
+pub use cone::{ConeCast, ConeCell, ConeFace};
+#[cfg(feature = "fixed")]
+pub use fixed::Fixed;
+pub use float::Float;
+pub use hex::{HexMap, HexOrientation, HexRayCastEngine};
+pub use layers::Layers;
+pub use mirrors::RaySegment;
+pub use objects::{Object, ObjectHit, ObjectShape, RayHit};
+pub use occupancy::OccupancyGrid;
+pub use portals::{Portal, PortalTransform};
+pub use sdf::SignedDistanceField;
+pub use sensor::{RangeSensor, Reading, Scan, SensorNoise};
+pub use shapes::CellShape;
+pub use spatial_hash::SpatialHash;
+pub use spans::SpanKind;
+pub use storeys::Storeys;
+pub use sweep::BoxHit;
+pub use voxel::{RayCastEngine3D, VoxelFace, VoxelMap, VoxelRayData};
+
 /// trait to use to make your own custom structure for maps
 pub trait Map {
     fn get_cell(&self, x: usize, y: usize) -> Option<u32>;
     fn get_size(&self) -> (usize, usize);
+
+    /// the height of the wall in the given cell, walls are 1 unit tall unless overridden
+    fn get_height(&self, _x: usize, _y: usize) -> f32 {
+        1.
+    }
+
+    /// the height of the floor in the given cell, walls stand on top of the floor
+    fn get_floor_height(&self, _x: usize, _y: usize) -> f32 {
+        0.
+    }
+
+    /// the height of the ceiling in the given cell, cells are open to the sky unless overridden
+    fn get_ceil_height(&self, _x: usize, _y: usize) -> f32 {
+        f32::INFINITY
+    }
+
+    /// the shape of the wall in the given cell, walls fill their whole cell unless overridden
+    fn get_shape(&self, _x: usize, _y: usize) -> CellShape {
+        CellShape::Block
+    }
+
+    /// the portal in the given cell, rays entering a portal cell carry on from its target instead
+    fn get_portal(&self, _x: usize, _y: usize) -> Option<Portal> {
+        None
+    }
+
+    /// if the wall in the given cell is a mirror that rays bounce off of
+    fn is_mirror(&self, _x: usize, _y: usize) -> bool {
+        false
+    }
+}
+
+/// lets a borrowed map be used anywhere a map is, like when
+/// making an engine for one storey of a stacked map
+impl<M: Map + ?Sized> Map for &M {
+    fn get_cell(&self, x: usize, y: usize) -> Option<u32> {
+        (**self).get_cell(x, y)
+    }
+
+    fn get_size(&self) -> (usize, usize) {
+        (**self).get_size()
+    }
+
+    fn get_height(&self, x: usize, y: usize) -> f32 {
+        (**self).get_height(x, y)
+    }
+
+    fn get_floor_height(&self, x: usize, y: usize) -> f32 {
+        (**self).get_floor_height(x, y)
+    }
+
+    fn get_ceil_height(&self, x: usize, y: usize) -> f32 {
+        (**self).get_ceil_height(x, y)
+    }
+
+    fn get_shape(&self, x: usize, y: usize) -> CellShape {
+        (**self).get_shape(x, y)
+    }
+
+    fn get_portal(&self, x: usize, y: usize) -> Option<Portal> {
+        (**self).get_portal(x, y)
+    }
+
+    fn is_mirror(&self, x: usize, y: usize) -> bool {
+        (**self).is_mirror(x, y)
+    }
 }
 
 /// holds information useful when looking at a casted ray
 #[derive(Default, Clone, Copy)]
-pub struct RayData {
+pub struct RayData<F = f32> {
     /// the length of the ray from the starting position to when it collided
-    pub ray_length: f32,
+    pub ray_length: F,
 
     /// the value in the map cell the ray collided with, or None if the ray did not collide
     pub hit_val: Option<u32>,
 
     /// the angle of the ray
-    pub ray_angle: f32,
+    pub ray_angle: F,
 
     /// the starting position of the ray
-    pub ray_position: (f32, f32),
+    pub ray_position: (F, F),
 
     /// a unit vector for the direction the ray traveled
-    pub ray_direction: (f32, f32),
+    pub ray_direction: (F, F),
 
     /// boolean for if the ray collided with a vertical wall
     pub collided_vertical: bool,
+
+    /// the bottom and top heights of the wall the ray collided with,
+    /// for floor and ceiling spans both are the height of the surface
+    pub wall_span: (F, F),
+
+    /// the length of the ray when it leaves the cell it collided with,
+    /// useful for drawing the tops of walls shorter than the camera
+    pub exit_length: F,
+
+    /// the map cell the ray collided with
+    pub hit_cell: (i32, i32),
+
+    /// what part of the cell the ray collided with
+    pub span_kind: SpanKind,
+
+    /// the lowest and highest slopes (height above the camera over distance) that the
+    /// span can be seen at, only the part of the span between them is visible
+    pub view_slopes: (F, F),
+
+    /// the storey of a stacked map or the layer of a layered map the ray collided in,
+    /// always 0 for a single map
+    pub layer: usize,
+
+    /// how far along the wall the ray collided from 0 to 1, useful
+    /// for picking which column of a texture to draw
+    pub texture_u: F,
+
+    /// a unit vector pointing out of the wall the ray collided with,
+    /// back towards the side the ray came from
+    pub normal: (F, F),
+
+    /// moves points from the space the ray started in to the space it collided in, the normal
+    /// is already in that space. this only isnt the identity if the ray went through portals
+    pub transform: PortalTransform<F>,
+
+    /// where the last segment of the ray would have started if it was a straight line and
+    /// the direction it goes in, after any portals and mirrors. points along the end of the
+    /// ray are at `local_origin + local_direction * length`
+    pub local_origin: (F, F),
+    pub local_direction: (F, F),
+
+    /// how many times the ray bounced off of mirrors
+    pub bounces: u32,
 }
 
-/// ray cast engine to hold a map and allow the user to cast rays from any point in the map
-pub struct RayCastEngine<T: Map> {
+/// ray cast engine to hold a map and allow the user to cast rays from any point in the map,
+/// rays are casted with f32 unless another `Float` is picked with `with_precision`. only
+/// the plain casts work with other floats, see `with_precision` for which ones
+pub struct RayCastEngine<T, F = f32> {
     pub map: T,
     pub map_size: (usize, usize),
+
+    /// how many times a ray can bounce off of mirrors before they act like normal walls
+    pub max_bounces: u32,
+
+    /// the width and height of each cell in world space along with where the top left corner of the
+    /// map is. positions, angles and lengths given to and returned from the engine are in world space
+    /// while cells and portal transforms stay in map space. shapes are stretched along with their cells
+    pub cell_size: (F, F),
+    pub origin: (F, F),
+
+    /// lets `cast_ray` jump over empty parts of the map, see `build_distance_field`
+    pub distance_field: Option<DistanceField>,
 }
 
-impl<T: Map + std::marker::Sync> RayCastEngine<T> {
+impl<T> RayCastEngine<T> {
     /// creates a new engine with the provided map.
     /// maps are 1D vectors so user must provide the size
     /// of the map for use during the ray cast process
     pub fn new(map: T, map_size: (usize, usize)) -> Self {
-        Self { map, map_size }
+        Self::with_precision(map, map_size)
+    }
+}
+
+impl<T, F: Float> RayCastEngine<T, F> {
+    /// creates a new engine the same as `new` that casts rays with the float type `F`, like
+    /// `RayCastEngine::<_, f64>::with_precision(map, map_size)` for large maps.
+    /// only `cast_ray`, `cast_ray_path`, `build_distance_field` and the conversions between world and
+    /// grid space can be used with any float, and `cast_rays_multi` with f32 and f64. spans, storeys,
+    /// layers, objects, the spatial hash, packets, cones, swept boxes, range sensors, occupancy grids
+    /// and the fixed point casts all need an f32 engine. the heights from `Map` are always f32 too,
+    /// so `wall_span` is only as precise as an f32 whatever `F` is
+    pub fn with_precision(map: T, map_size: (usize, usize)) -> Self {
+        Self {
+            map,
+            map_size,
+            max_bounces: 8,
+            cell_size: (F::ONE, F::ONE),
+            origin: (F::ZERO, F::ZERO),
+            distance_field: None,
+        }
+    }
+
+    /// checks if a cell is inside of the map
+    fn in_bounds(&self, cell: (i32, i32)) -> bool {
+        cell.0 >= 0
+            && cell.0 < self.map_size.0 as i32
+            && cell.1 >= 0
+            && cell.1 < self.map_size.1 as i32
+    }
+}
+
+impl<T: Map + std::marker::Sync, F: Float> RayCastEngine<T, F> {
+    /// casts a single ray from the given position with the
+    /// given angle and returns information about the casted ray.
+    /// the ray stops at the first wall it hits no matter how tall it is
+    pub fn cast_ray(&self, pos: (F, F), angle: F, max_distance: F) -> RayData<F> {
+        self.trace_ray(pos, angle, max_distance, None)
+    }
+
+    /// casts a ray the same as `cast_ray`, adding each segment of its path to `segments` if given
+    fn trace_ray(&self, pos: (F, F), angle: F, max_distance: F, segments: Option<&mut Vec<RaySegment<F>>>) -> RayData<F> {
+        // the ray is walked through the map where each cell is 1 unit wide and moved back into world space after
+        let grid = self.grid_ray(pos, angle);
+        let walker = DdaWalker::new(grid.pos, grid.dir);
+        self.walk_ray(&grid, walker, (F::ZERO, false), max_distance, segments)
+    }
+
+    /// carries on casting a ray from the cell `walker` is in, given the length of the ray and if the edge
+    /// was vertical when it went into that cell. this is where rays from a packet are finished off
+    fn walk_ray(
+        &self,
+        grid: &GridRay<F>,
+        mut walker: DdaWalker<F>,
+        entered: (F, bool),
+        max_distance: F,
+        mut segments: Option<&mut Vec<RaySegment<F>>>,
+    ) -> RayData<F> {
+        let max_distance = grid.length(max_distance);
+
+        // the value in the map that the ray collided with along with where it hit the wall
+        let mut hit = None;
+
+        // the ray can hit a thin wall in the cell it starts in, but not the edges of a full one
+        if let Some(current_cell) = self.cell_at(walker.cell) {
+            let shape = self.map.get_shape(walker.cell.0 as usize, walker.cell.1 as usize);
+            if current_cell > 0 && shape != CellShape::Block {
+                hit = shape.intersect(walker.cell, walker.origin, walker.dir, (F::ZERO, walker.exit_length()), false).map(|h| (current_cell, h));
+            }
+        }
+
+        // initialize info needed for the casting process
+        let (mut distance, mut collided_horizontal) = entered;
+        let mut bounces = 0;
+
+        // the length of the ray at the start of the segment it is currently on
+        let mut segment_start = F::ZERO;
+        let mut add_segment = |origin: (F, F), dir: (F, F), start: F, end: F| {
+            if let Some(segments) = segments.as_deref_mut() {
+                segments.push(grid.segment_to_world(RaySegment::new(origin, dir, start, end)));
+            }
+        };
+
+        loop {
+            while hit.is_none() && distance < max_distance {
+                (distance, collided_horizontal) = self.advance(&mut walker, max_distance);
+
+                let (origin, dir) = (walker.origin, walker.dir);
+                collided_horizontal = walker.follow_portals(collided_horizontal, |cell| self.portal_at(cell));
+                if walker.origin != origin {
+                    add_segment(origin, dir, segment_start, distance);
+                    segment_start = distance;
+                }
+
+                // checks if the current cell in the map is a wall, walls that dont fill
+                // their cell can still be missed if the ray passes beside them
+                match self.cell_at(walker.cell) {
+                    Some(current_cell) => {
+                        if current_cell > 0 {
+                            let shape = self.map.get_shape(walker.cell.0 as usize, walker.cell.1 as usize);
+                            hit = shape
+                                .intersect(walker.cell, walker.origin, walker.dir, (distance, walker.exit_length()), collided_horizontal)
+                                .map(|h| (current_cell, h));
+                        }
+                    }
+                    // we are outside the map, break early
+                    None => break,
+                }
+            }
+
+            // bounces off of mirrors until the ray runs out of bounces
+            match hit {
+                Some((_, h)) if bounces < self.max_bounces && self.mirror_at(walker.cell) => {
+                    add_segment(walker.origin, walker.dir, segment_start, h.distance);
+                    segment_start = h.distance;
+                    distance = h.distance;
+
+                    let shape = self.map.get_shape(walker.cell.0 as usize, walker.cell.1 as usize);
+                    walker.bounce(&h, shape);
+                    bounces += 1;
+                    hit = None;
+                }
+                _ => break,
+            }
+        }
+
+        let mut ray = RayData {
+            ray_length: distance,
+            hit_val: None,
+            ray_angle: F::ZERO,
+            ray_position: grid.pos,
+            ray_direction: grid.dir,
+            collided_vertical: collided_horizontal,
+            wall_span: (F::ZERO, F::ZERO),
+            exit_length: walker.exit_length(),
+            hit_cell: walker.cell,
+            span_kind: SpanKind::Wall,
+            view_slopes: (F::NEG_INFINITY, F::INFINITY),
+            layer: 0,
+            texture_u: edge_u(walker.origin, walker.dir, distance, collided_horizontal),
+            normal: edge_normal(walker.dir, collided_horizontal),
+            transform: walker.transform,
+            local_origin: walker.origin,
+            local_direction: walker.dir,
+            bounces,
+        };
+
+        if let Some((val, h)) = hit {
+            let (x, y) = (walker.cell.0 as usize, walker.cell.1 as usize);
+            let floor = self.map.get_floor_height(x, y);
+            ray.ray_length = h.distance;
+            ray.hit_val = Some(val);
+            ray.collided_vertical = h.collided_vertical;
+            ray.wall_span = (F::from_f32(floor), F::from_f32(floor + self.map.get_height(x, y)));
+            ray.texture_u = h.texture_u;
+            ray.normal = h.normal;
+        }
+
+        add_segment(walker.origin, walker.dir, segment_start, ray.ray_length);
+
+        grid.to_world(ray)
+    }
+
+    /// gets the value of a cell, or None if the cell is outside of the map
+    fn cell_at(&self, cell: (i32, i32)) -> Option<u32> {
+        if self.in_bounds(cell) {
+            self.map.get_cell(cell.0 as usize, cell.1 as usize)
+        } else {
+            None
+        }
     }
 
+    /// checks if the wall in a cell is a mirror, cells outside of the map never are
+    fn mirror_at(&self, cell: (i32, i32)) -> bool {
+        self.in_bounds(cell) && self.map.is_mirror(cell.0 as usize, cell.1 as usize)
+    }
+
+    /// gets the portal in a cell, or None if there isnt one or the cell is outside of the map
+    fn portal_at(&self, cell: (i32, i32)) -> Option<Portal> {
+        if self.in_bounds(cell) {
+            self.map.get_portal(cell.0 as usize, cell.1 as usize)
+        } else {
+            None
+        }
+    }
+}
+
+impl<T: Map + std::marker::Sync> RayCastEngine<T> {
     /// takes a vector of angles for rays to be casted at and
-    /// uses rayon to cast them all with multithreading then 
-    /// stores them in a hashmap along with their angles index
+    /// uses rayon to cast them all with multithreading then
+    /// returns them in a vector along with their angles index.
+    /// the rays arent in the same order as the angles.
+    /// neighbouring angles are casted together in packets, see `cast_ray_packet8`
     pub fn cast_rays_multi(&self, pos: (f32, f32), angles: Vec<f32>, max_distance: f32) -> Vec<(usize, RayData)> {
-        let mut rays = Vec::new();
+        // packets step through every cell, so rays skipping with a distance field on their own are faster
+        if self.distance_field.is_some() {
+            return cast_multi(&angles, |angle| self.cast_ray(pos, *angle, max_distance));
+        }
 
-        let (tx, rx) = mpsc::channel();
+        let packets: Vec<&[f32]> = angles.chunks(PACKET_SIZE).collect();
+        cast_multi(&packets, |packet| self.cast_packet(pos, packet, max_distance))
+            .into_iter()
+            .flat_map(|(i, rays)| rays.into_iter().enumerate().map(move |(j, ray)| (i * PACKET_SIZE + j, ray)))
+            .collect()
+    }
+}
 
-        angles.par_iter().enumerate().for_each_with(tx, |tx, (i, angle)| tx.send((i, self.cast_ray(pos, *angle, max_distance))).unwrap());
+impl<T: Map + std::marker::Sync> RayCastEngine<T, f64> {
+    /// the same as `cast_rays_multi` on an f32 engine, but every ray is casted
+    /// on its own since packets only work with f32
+    pub fn cast_rays_multi(&self, pos: (f64, f64), angles: Vec<f64>, max_distance: f64) -> Vec<(usize, RayData<f64>)> {
+        cast_multi(&angles, |angle| self.cast_ray(pos, *angle, max_distance))
+    }
+}
 
-        for (i, ray) in rx {
-            rays.push((i, ray));
-        }
+/// uses rayon to run the cast function on every input with multithreading,
+/// the results are returned along with the index of the input they came from
+fn cast_multi<I: Sync, R: Send>(inputs: &[I], cast: impl Fn(&I) -> R + Sync) -> Vec<(usize, R)> {
+    let mut rays = Vec::new();
 
-        rays
+    let (tx, rx) = mpsc::channel();
+
+    inputs.par_iter().enumerate().for_each_with(tx, |tx, (i, input)| tx.send((i, cast(input))).unwrap());
+
+    for (i, ray) in rx {
+        rays.push((i, ray));
     }
 
-    /// casts a single ray from the given position with the
-    /// given angle and returns information about the casted ray
-    pub fn cast_ray(&self, pos: (f32, f32), angle: f32, max_distance: f32) -> RayData {
-        // option to hold the the value in the map that the ray collided with
-        let mut hit_val: Option<u32> = None;
+    rays
+}
 
-        // makes a normalized vector with the provided angle
-        let ray_dir = (f32::cos(angle), f32::sin(angle));
+/// the lengths where a ray enters and leaves a box, or None if it misses the box or the box is behind it.
+/// the ray enters at a negative length when it starts inside the box
+fn box_lengths(pos: (f32, f32), dir: (f32, f32), min: (f32, f32), max: (f32, f32)) -> Option<(f32, f32)> {
+    let axis = |pos: f32, dir: f32, min: f32, max: f32| {
+        if dir == 0. {
+            return (min..=max).contains(&pos).then_some((f32::NEG_INFINITY, f32::INFINITY));
+        }
+        let (a, b) = ((min - pos) / dir, (max - pos) / dir);
+        Some((a.min(b), a.max(b)))
+    };
+    let (near_x, far_x) = axis(pos.0, dir.0, min.0, max.0)?;
+    let (near_y, far_y) = axis(pos.1, dir.1, min.1, max.1)?;
+    let (near, far) = (near_x.max(near_y), far_x.min(far_y));
+    (near <= far && far >= 0.).then_some((near, far))
+}
+
+/// steps a ray through the map one cell at a time
+#[derive(Clone, Copy)]
+struct DdaWalker<F = f32> {
+    /// the cell the ray is currently in
+    cell: (i32, i32),
+
+    /// the grid based x/y movement of the ray
+    step: (i32, i32),
+
+    /// the length of the ray needed to cross one cell in each direction
+    ray_unit_step_size: (F, F),
+
+    /// the length of the ray when it first crosses a cell edge in each direction
+    first_length: (F, F),
+
+    /// how many cell edges the ray has crossed in each direction
+    crossed: (u32, u32),
+
+    /// the length of the ray when it next crosses a cell edge in each direction, this is always
+    /// worked out from the first length and the number of edges crossed instead of adding up
+    /// steps so skipping over many cells at once ends up at exactly the same length
+    ray_length_1d: (F, F),
+
+    /// where the ray started and the direction it is going in the space it is currently in,
+    /// these only change when the ray goes through a portal
+    origin: (F, F),
+    dir: (F, F),
 
+    /// moves points from where the ray started to the space it is currently in
+    transform: PortalTransform<F>,
+}
+
+/// how many portals in a row a ray can go through without crossing a cell, stops
+/// rays from getting stuck when portals lead straight into other portals
+const MAX_PORTAL_HOPS: usize = 8;
+
+impl<F: Float> DdaWalker<F> {
+    fn new(pos: (F, F), ray_dir: (F, F)) -> Self {
+        Self::new_in_cell(pos, ray_dir, (pos.0.floor().to_i32(), pos.1.floor().to_i32()), PortalTransform::default())
+    }
+
+    /// makes a walker for a ray starting at `pos` that is currently in `cell`
+    fn new_in_cell(pos: (F, F), ray_dir: (F, F), cell: (i32, i32), transform: PortalTransform<F>) -> Self {
         // calculate each step size for the ray for each unit cell in the map
         let ray_unit_step_size = (
-            f32::sqrt(1. + (ray_dir.1 / ray_dir.0) * (ray_dir.1 / ray_dir.0)),
-            f32::sqrt(1. + (ray_dir.0 / ray_dir.1) * (ray_dir.0 / ray_dir.1)),
+            (F::ONE + (ray_dir.1 / ray_dir.0) * (ray_dir.1 / ray_dir.0)).sqrt(),
+            (F::ONE + (ray_dir.0 / ray_dir.1) * (ray_dir.0 / ray_dir.1)).sqrt(),
         );
 
-        // initialize information about the ray like its starting position and length
-        let mut current_map_cell = (pos.0 as i32, pos.1 as i32);
-        #[allow(non_snake_case)]
-        let mut ray_length_1D: (f32, f32) = (0., 0.);
-
-        // tuple for storing the grid based x/y movement of the ray
-        let mut step: (i32, i32) = (0, 0);
+        let mut step = (0, 0);
+        let mut ray_length_1d = (F::ZERO, F::ZERO);
 
         // does the first step manually since the position
         // can be in a cell instead of on its edges
-        if ray_dir.0 < 0. {
+        if ray_dir.0 < F::ZERO {
             step.0 = -1;
-            ray_length_1D.0 = (pos.0 - current_map_cell.0 as f32) * ray_unit_step_size.0;
+            ray_length_1d.0 = (pos.0 - F::from_i32(cell.0)) * ray_unit_step_size.0;
         } else {
             step.0 = 1;
-            ray_length_1D.0 = ((current_map_cell.0 + 1) as f32 - pos.0) * ray_unit_step_size.0;
+            ray_length_1d.0 = (F::from_i32(cell.0 + 1) - pos.0) * ray_unit_step_size.0;
         }
 
-        if ray_dir.1 < 0. {
+        if ray_dir.1 < F::ZERO {
             step.1 = -1;
-            ray_length_1D.1 = (pos.1 - current_map_cell.1 as f32) * ray_unit_step_size.1;
+            ray_length_1d.1 = (pos.1 - F::from_i32(cell.1)) * ray_unit_step_size.1;
         } else {
             step.1 = 1;
-            ray_length_1D.1 = ((current_map_cell.1 + 1) as f32 - pos.1) * ray_unit_step_size.1;
+            ray_length_1d.1 = (F::from_i32(cell.1 + 1) - pos.1) * ray_unit_step_size.1;
         }
 
-        // initialize info needed for the casting process
-        let mut tile_found = false;
-        let mut distance = 0_f32;
-        let mut collided_horizontal = false;
-        while !tile_found && distance < max_distance {
-            // walk 1 unit along the ray
-            // and check if the x length
-            // or y length are shorter
-            if ray_length_1D.0 < ray_length_1D.1 {
-                // if the x length is shorter, takes 1
-                // step in the x direction on the ray
-                current_map_cell.0 += step.0;
-                distance = ray_length_1D.0;
-                ray_length_1D.0 += ray_unit_step_size.0;
-                collided_horizontal = true;
-            } else {
-                // if the x length is shorter, takes 1
-                // step in the y direction on the ray
-                current_map_cell.1 += step.1;
-                distance = ray_length_1D.1;
-                ray_length_1D.1 += ray_unit_step_size.1;
-                collided_horizontal = false;
-            }
+        Self {
+            cell,
+            step,
+            ray_unit_step_size,
+            first_length: ray_length_1d,
+            crossed: (0, 0),
+            ray_length_1d,
+            origin: pos,
+            dir: ray_dir,
+            transform,
+        }
+    }
 
-            // checks if the current cell in the map is a wall
-            if current_map_cell.0 >= 0
-                && current_map_cell.0 < self.map_size.0 as i32
-                && current_map_cell.1 >= 0
-                && current_map_cell.1 < self.map_size.1 as i32
-            {
-                let current_cell = self.map.get_cell(current_map_cell.0 as usize, current_map_cell.1 as usize).unwrap();//[(current_map_cell.1 * self.map_size.0 as i32 + current_map_cell.0) as usize];
-                if current_cell > 0 {
-                    hit_val = Some(current_cell);
-                    tile_found = true;
-                }
-            } else {
-                // we are outside the map, break early
-                break;
-            }
+    /// walks into the next cell along the ray, returns the length of the ray
+    /// at the edge it crossed and if that edge was a vertical one
+    fn step(&mut self) -> (F, bool) {
+        // checks if the x length or y length are shorter
+        if self.ray_length_1d.0 < self.ray_length_1d.1 {
+            // if the x length is shorter, takes 1
+            // step in the x direction on the ray
+            self.cell.0 += self.step.0;
+            let distance = self.ray_length_1d.0;
+            self.crossed.0 += 1;
+            self.ray_length_1d.0 = self.crossing_length(self.crossed.0, true);
+            (distance, true)
+        } else {
+            // if the y length is shorter, takes 1
+            // step in the y direction on the ray
+            self.cell.1 += self.step.1;
+            let distance = self.ray_length_1d.1;
+            self.crossed.1 += 1;
+            self.ray_length_1d.1 = self.crossing_length(self.crossed.1, false);
+            (distance, false)
         }
+    }
 
-        RayData {
-            ray_length: distance,
-            hit_val,
-            ray_angle: angle,
-            ray_position: pos,
-            ray_direction: ray_dir,
-            collided_vertical: collided_horizontal,
+    /// the length of the ray when it crosses a cell edge in one direction after
+    /// crossing `crossed` other edges in that direction since it started
+    fn crossing_length(&self, crossed: u32, vertical: bool) -> F {
+        let (first, unit) = if vertical {
+            (self.first_length.0, self.ray_unit_step_size.0)
+        } else {
+            (self.first_length.1, self.ray_unit_step_size.1)
+        };
+
+        // rays going along an axis never cross its edges, this stops 0 * infinity from making NaN
+        if crossed == 0 {
+            first
+        } else {
+            first + F::from_u32(crossed) * unit
+        }
+    }
+
+    /// the length of the ray when it leaves the current cell
+    fn exit_length(&self) -> F {
+        self.ray_length_1d.0.min(self.ray_length_1d.1)
+    }
+
+    /// sends the ray through the portals in the cell it just entered until it is in a
+    /// cell without one, returns if the edge it entered through is now a vertical one
+    fn follow_portals(&mut self, mut collided_vertical: bool, portal_at: impl Fn((i32, i32)) -> Option<Portal>) -> bool {
+        for _ in 0..MAX_PORTAL_HOPS {
+            let portal = match portal_at(self.cell) {
+                Some(portal) => portal,
+                None => break,
+            };
+
+            let transform = portal.transform((self.cell.0 as usize, self.cell.1 as usize));
+            let target = (portal.target.0 as i32, portal.target.1 as i32);
+            *self = Self::new_in_cell(
+                transform.apply_point(self.origin),
+                transform.apply_dir(self.dir),
+                target,
+                self.transform.then(&transform),
+            );
+
+            // quarter turns swap which edges are vertical
+            if transform.rotation % 2 == 1 {
+                collided_vertical = !collided_vertical;
+            }
         }
+        collided_vertical
     }
 }
@@ -1,7 +1,10 @@
+mod controls;
+
+use controls::{Action, Controls, ControlsMenu, CONTROLS_FILE};
 use macroquad::prelude::*;
-use raycast_dda::{RayCastEngine, Map};
+use raycast_dda::{CellShape, RayCastEngine, Map, Portal, SpanKind, Storeys};
 // use serde_json::Value;
-use std::{collections::HashMap, f32::consts::PI};//, fs, io::Read};
+use std::{collections::{HashMap, HashSet}, f32::consts::PI};//, fs, io::Read};
 
 const WIDTH: i32 = 1280; // window width
 const HEIGHT: i32 = 720; // window height
@@ -9,13 +12,32 @@ const WIDTH_3D: f32 = 1.; // the width of each column when casting the rays and
 const VIEW_DISTANCE: f32 = 30.; // how many grid spaces the camera can see up to
 const BLOCK_SIZE: f32 = 64.; // the size of the textures used for the walls
 const PLAYER_MOVE_SPEED: f32 = 8.; // the players move speed
+const PLAYER_SPRINT_MULTIPLIER: f32 = 1.8; // how much faster the player moves while sprinting
 const PLAYER_TURN_SPEED: f32 = 2.; // the player turn speed
 const FOV: f32 = 60.; // the cameras fov in degrees
+const MAX_PITCH: f32 = 0.8; // how far the camera can look up or down in radians
+const PLAYER_EYE_HEIGHT: f32 = 0.5; // the height of the camera above the players feet while standing
+const PLAYER_CROUCH_EYE_HEIGHT: f32 = 0.28; // the height of the camera above the players feet while crouching
+const PLAYER_HEIGHT: f32 = 0.6; // how tall the player is while standing, used for ceiling collisions
+const PLAYER_CROUCH_HEIGHT: f32 = 0.35; // how tall the player is while crouching
+const PLAYER_CROUCH_SPEED: f32 = 0.5; // the move speed multiplier while crouching
+const JUMP_SPEED: f32 = 3.; // the upward speed the player starts a jump with
+const GRAVITY: f32 = 12.; // how fast the players vertical speed decreases while in the air
+const MAX_STEP_HEIGHT: f32 = 0.25; // the highest step the player can walk up without jumping
+const STOREY_HEIGHT: f32 = 1.; // how far apart the storeys of the level are stacked
+const DOOR_SPEED: f32 = 1.5; // how much of the way open a door slides each second
+const DOOR_OPEN_DISTANCE: f32 = 2.; // how close the player needs to be for doors to open
 
 struct World {
     map: Vec<u32>,
     floor: Vec<u32>,
     ceil: Vec<u32>,
+    heights: Vec<f32>,
+    floor_heights: Vec<f32>,
+    ceil_heights: Vec<f32>,
+    shapes: HashMap<(usize, usize), CellShape>,
+    portals: HashMap<(usize, usize), Portal>,
+    mirrors: HashSet<(usize, usize)>,
     map_size: (usize, usize),
 }
 
@@ -31,6 +53,86 @@ impl World {
     }
 }
 
+/// the vertical state of the player used for jumping, falling and crouching
+struct PlayerBody {
+    /// the height of the players feet off the floor
+    z: f32,
+    /// the players vertical speed
+    z_speed: f32,
+    on_ground: bool,
+    crouching: bool,
+    /// the current height of the camera above the players feet,
+    /// moves smoothly between the standing and crouching heights
+    eye_height: f32,
+}
+
+impl PlayerBody {
+    fn new() -> Self {
+        Self {
+            z: 0.,
+            z_speed: 0.,
+            on_ground: true,
+            crouching: false,
+            eye_height: PLAYER_EYE_HEIGHT,
+        }
+    }
+
+    /// how tall the player currently is
+    fn height(&self) -> f32 {
+        if self.crouching {
+            PLAYER_CROUCH_HEIGHT
+        } else {
+            PLAYER_HEIGHT
+        }
+    }
+
+    /// the height of the camera off the floor
+    fn camera_height(&self) -> f32 {
+        self.z + self.eye_height
+    }
+
+    /// applies gravity and the players jump/crouch input, `floor_height` and `ceil_height`
+    /// are the heights of the floor and ceiling of the cell the player is in
+    fn update(&mut self, jump: bool, crouch: bool, floor_height: f32, ceil_height: f32, delta_time: f32) {
+        // the player can always crouch, but can only stand back
+        // up if there is enough room above them to do so
+        if crouch {
+            self.crouching = true;
+        } else if self.crouching && self.z + PLAYER_HEIGHT <= ceil_height {
+            self.crouching = false;
+        }
+
+        if jump && self.on_ground {
+            self.z_speed = JUMP_SPEED;
+            self.on_ground = false;
+        }
+
+        self.z_speed -= GRAVITY * delta_time;
+        self.z += self.z_speed * delta_time;
+
+        // lands on the floor, this also moves the player up any steps they walk onto
+        if self.z <= floor_height {
+            self.z = floor_height;
+            self.z_speed = 0.;
+            self.on_ground = true;
+        }
+
+        // hitting the ceiling stops any upward movement
+        if self.z + self.height() > ceil_height {
+            self.z = (ceil_height - self.height()).max(floor_height);
+            self.z_speed = self.z_speed.min(0.);
+        }
+
+        let target_eye = if self.crouching { PLAYER_CROUCH_EYE_HEIGHT } else { PLAYER_EYE_HEIGHT };
+        let eye_step = (PLAYER_EYE_HEIGHT - PLAYER_CROUCH_EYE_HEIGHT) * 8. * delta_time;
+        if self.eye_height < target_eye {
+            self.eye_height = (self.eye_height + eye_step).min(target_eye);
+        } else {
+            self.eye_height = (self.eye_height - eye_step).max(target_eye);
+        }
+    }
+}
+
 impl Map for World {
     fn get_cell(&self, x: usize, y: usize) -> Option<u32> {
         let index = y * self.map_size.0 + x;
@@ -40,6 +142,37 @@ impl Map for World {
     fn get_size(&self) -> (usize, usize) {
         self.map_size
     }
+
+    fn get_height(&self, x: usize, y: usize) -> f32 {
+        let index = y * self.map_size.0 + x;
+        self.heights.get(index).copied().unwrap_or(1.)
+    }
+
+    fn get_floor_height(&self, x: usize, y: usize) -> f32 {
+        let index = y * self.map_size.0 + x;
+        self.floor_heights.get(index).copied().unwrap_or(0.)
+    }
+
+    /// cells without a ceiling texture are open to the sky
+    fn get_ceil_height(&self, x: usize, y: usize) -> f32 {
+        let index = y * self.map_size.0 + x;
+        match self.ceil.get(index) {
+            Some(0) | None => f32::INFINITY,
+            Some(_) => self.ceil_heights.get(index).copied().unwrap_or(1.),
+        }
+    }
+
+    fn get_shape(&self, x: usize, y: usize) -> CellShape {
+        self.shapes.get(&(x, y)).copied().unwrap_or_default()
+    }
+
+    fn get_portal(&self, x: usize, y: usize) -> Option<Portal> {
+        self.portals.get(&(x, y)).copied()
+    }
+
+    fn is_mirror(&self, x: usize, y: usize) -> bool {
+        self.mirrors.contains(&(x, y))
+    }
 }
 
 fn window_conf() -> Conf {
@@ -67,6 +200,7 @@ async fn main() {
     let sky = load_texture("sky.png").await.unwrap();
     let sky_width = screen_width();
     let sky_height = sky_width / 2.;
+    let sky_color = get_average_texture_color(&sky);
 
     let bricks = load_texture("bricks.png").await.unwrap();
     let bricks_image = bricks.get_texture_data();
@@ -94,12 +228,18 @@ async fn main() {
     );
     let mut player_angle = 0.;
 
+    // how far the camera is looking up or down, in radians
+    let mut player_pitch: f32 = 0.;
+
+    // the players height off the floor, where walls are 1 unit tall
+    let mut body = PlayerBody::new();
+
     // the map that the can player move through and look around in
     let map: Vec<u32> = vec!(
         2,2,2,2,2,2,2,2,2,2,2,2,2,2,2,2,2,2,2,2,2,
         1,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,2,
-        2,2,2,2,2,0,2,2,2,2,2,2,2,2,2,2,2,2,2,2,2,
-        2,0,0,0,0,0,2,0,0,0,0,0,2,0,0,0,0,0,0,0,2,
+        2,2,2,2,2,3,2,2,2,2,2,2,2,2,2,2,2,2,2,2,2,
+        2,0,0,0,0,0,2,2,0,0,0,2,2,0,0,0,0,0,0,0,2,
         2,0,2,2,2,2,2,0,2,2,2,0,2,0,2,2,2,2,2,0,2,
         2,0,2,0,0,0,0,0,0,0,2,0,2,0,2,0,0,0,0,0,2,
         2,0,2,0,2,2,2,2,2,2,2,0,2,0,2,2,2,2,2,2,2,
@@ -115,7 +255,7 @@ async fn main() {
         2,2,2,2,2,0,2,2,2,0,2,2,2,0,2,2,2,2,2,0,2,
         2,0,2,0,0,0,2,0,2,0,0,0,0,0,2,0,0,0,0,0,2,
         2,0,2,0,2,2,2,0,2,2,2,2,2,2,2,2,2,0,2,0,2,
-        2,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,2,0,2,
+        2,0,0,0,0,0,2,0,0,0,2,0,0,0,2,0,0,0,2,0,2,
         2,2,2,2,2,2,2,2,2,2,2,2,2,2,2,2,2,2,2,3,2
     );
 
@@ -146,8 +286,8 @@ async fn main() {
     let ceil: Vec<u32> = vec!(
         2,2,2,2,2,2,2,2,2,2,2,2,2,2,2,2,2,2,2,2,2,
         1,2,2,2,2,2,2,2,2,2,2,2,2,2,2,2,2,2,2,2,2,
-        2,2,2,2,2,0,2,2,2,2,2,2,2,2,2,2,2,2,2,2,2,
-        2,2,0,0,0,0,2,0,0,0,0,0,2,0,0,0,0,0,0,0,2,
+        2,2,0,0,0,0,2,2,2,2,2,2,2,2,2,2,2,2,2,2,2,
+        2,0,0,0,0,0,2,0,0,0,0,0,2,0,0,0,0,0,0,0,2,
         2,2,2,2,2,2,2,0,2,2,2,0,2,0,2,2,2,2,2,0,2,
         2,2,2,0,0,0,0,0,0,0,2,0,2,0,2,0,0,0,0,0,2,
         2,2,2,0,2,2,2,2,2,2,2,0,2,0,2,2,2,2,2,2,2,
@@ -179,10 +319,103 @@ async fn main() {
         21//level[1]["__cHei"].as_u64().unwrap() as usize,
     );
 
+    // the height of each wall, most are 1 unit tall but there are a few
+    // low walls you can see over and some taller ones to see past them
+    let mut heights: Vec<f32> = vec![1.; map_size.0 * map_size.1];
+    for (x, y, height) in [(2, 2, 0.4), (3, 2, 0.4), (4, 2, 0.4), (8, 4, 2.5), (9, 4, 2.5), (10, 4, 2.5)] {
+        heights[y * map_size.0 + x] = height;
+    }
+
+    // the floor and ceiling heights of each cell, the first corridor has some stairs
+    // up to a platform, a pit you need to jump out of and a low passage to crouch through
+    let mut floor_heights: Vec<f32> = vec![0.; map_size.0 * map_size.1];
+    let mut ceil_heights: Vec<f32> = vec![1.; map_size.0 * map_size.1];
+    for (x, height) in [(9, 0.1), (10, 0.2), (11, 0.3), (12, 0.3), (13, 0.3), (14, 0.15), (16, -0.3)] {
+        floor_heights[map_size.0 + x] = height;
+    }
+    for (x, height) in [(6, 0.5), (7, 0.5), (17, 1.6), (18, 1.6)] {
+        ceil_heights[map_size.0 + x] = height;
+    }
+
+    // stairs in the third corridor lead up onto the roof
+    for (x, height) in [(4, 0.2), (3, 0.4), (2, 0.6), (1, 0.8)] {
+        floor_heights[3 * map_size.0 + x] = height;
+    }
+
+    // a sliding door between the first two corridors and a couple of alcoves set back into the wall
+    let doors = [(5, 2)];
+    let mut shapes = HashMap::new();
+    for door in doors {
+        shapes.insert(door, CellShape::Door { vertical: false, offset: 0.5, open: 0. });
+    }
+    for alcove in [(12, 2), (13, 2)] {
+        shapes.insert(alcove, CellShape::Thin { vertical: false, offset: 0.6 });
+    }
+
+    // diagonal walls cutting the corners of the second corridor and some round pillars along the last one
+    shapes.insert((7, 3), CellShape::Segment { start: (0., 1.), end: (1., 0.) });
+    shapes.insert((11, 3), CellShape::Segment { start: (0., 0.), end: (1., 1.) });
+    for pillar in [(6, 19), (10, 19), (14, 19)] {
+        shapes.insert(pillar, CellShape::Circle { center: (0.5, 0.5), radius: 0.25 });
+    }
+
+    // a pair of portals joining the ends of two corridors, walking east into
+    // one comes out heading north from the other and back again
+    let portals = HashMap::from([
+        ((4, 9), Portal { target: (11, 9), rotation: 3 }),
+        ((11, 10), Portal { target: (3, 9), rotation: 1 }),
+    ]);
+
+    // mirrors at both ends of a corridor reflect each other over and over
+    let mirrors = HashSet::from([(2, 5), (10, 5)]);
+
     let map = World {
         map,
         floor,
         ceil,
+        heights,
+        floor_heights,
+        ceil_heights,
+        shapes,
+        portals,
+        mirrors,
+        map_size
+    };
+
+    // the roof is a second storey on top of the first, it has a low wall around its edge,
+    // the tops of the tall walls and a small tower. it only has a floor above cells of
+    // the first storey with a ceiling, everywhere else you can fall back down
+    let mut roof_map: Vec<u32> = vec![0; map_size.0 * map_size.1];
+    let mut roof_heights: Vec<f32> = vec![1.; map_size.0 * map_size.1];
+    for y in 0..map_size.1 {
+        for x in 0..map_size.0 {
+            if x == 0 || y == 0 || x == map_size.0 - 1 || y == map_size.1 - 1 {
+                roof_map[y * map_size.0 + x] = 2;
+                roof_heights[y * map_size.0 + x] = 0.3;
+            }
+        }
+    }
+    for (x, y, val, height) in [(8, 4, 2, 1.5), (9, 4, 2, 1.5), (10, 4, 2, 1.5), (2, 13, 1, 1.), (3, 13, 1, 1.), (2, 14, 1, 1.), (3, 14, 1, 1.)] {
+        roof_map[y * map_size.0 + x] = val;
+        roof_heights[y * map_size.0 + x] = height;
+    }
+
+    // the roof sits on top of the high ceilings at the end of the first corridor
+    let mut roof_floor_heights: Vec<f32> = vec![0.; map_size.0 * map_size.1];
+    for x in [17, 18] {
+        roof_floor_heights[map_size.0 + x] = 0.6;
+    }
+
+    let roof = World {
+        map: roof_map,
+        floor: vec![2; map_size.0 * map_size.1],
+        ceil: vec![0; map_size.0 * map_size.1],
+        heights: roof_heights,
+        floor_heights: roof_floor_heights,
+        ceil_heights: vec![1.; map_size.0 * map_size.1],
+        shapes: HashMap::new(),
+        portals: HashMap::new(),
+        mirrors: HashSet::new(),
         map_size
     };
 
@@ -190,97 +423,165 @@ async fn main() {
     let plane_dist: f32 = (screen_width() / 2.) / (FOV / 2.).to_radians().tan();
 
     // the total number of rays/columns to draw
-    let total_num_of_cols = screen_width() / WIDTH_3D as f32;
+    let total_num_of_cols = screen_width() / WIDTH_3D;
 
-    let engine = RayCastEngine::new(map, map_size);
-
-    // the texture to draw each column
-    let mut texture;
+    let mut engine = RayCastEngine::new(Storeys::new(vec![map, roof], STOREY_HEIGHT), map_size);
 
     let mut floor_image = Image::gen_image_color(WIDTH as u16, HEIGHT as u16, Color::new(0., 0., 0., 0.));
     let floor_tex = Texture2D::from_image(&floor_image);
 
-    // used in some way for rendering the floor
-    // im not super clear on what this does and the formula
-    // ive come up with doesnt seem to be perfect, but
-    // its close enough for the most part so ill leave it for now
-    let ar = (plane_dist / 4.).floor();
+    // samples the floor/ceiling texture for the given cell value at a point in the world
+    let surface_pixel = |val: Option<u32>, tx: f32, ty: f32| {
+        let (px, py) = (((tx % 1.) * 64.) as u32, ((ty % 1.) * 64.) as u32);
+        match val {
+            Some(1) => bricks_image.get_pixel(px, py),
+            Some(2) => blackstone_image.get_pixel(px, py),
+            _ => plank_image.get_pixel(px, py),
+        }
+    };
+
+    let mut controls = Controls::load(CONTROLS_FILE);
+    let mut menu = ControlsMenu::default();
+    set_cursor_grab(true);
+    show_mouse(false);
+    let mut last_mouse = mouse_position();
 
     loop {
         floor_image = Image::gen_image_color(WIDTH as u16, HEIGHT as u16, Color::new(0., 0., 0., 0.));
         clear_background(BLACK);
         let delta_time = get_frame_time();
 
-        // updates the players viewing angle
-        if is_key_down(KeyCode::A) {
-            player_angle -= PLAYER_TURN_SPEED * delta_time;
-        }
-        if is_key_down(KeyCode::D) {
-            player_angle += PLAYER_TURN_SPEED * delta_time;
+        // opens/closes the controls menu, the mouse is
+        // only captured for looking while the menu is closed
+        if is_key_pressed(KeyCode::Tab) && !menu.is_rebinding() {
+            menu.open = !menu.open;
+            set_cursor_grab(!menu.open);
+            show_mouse(menu.open);
         }
 
-        // limits the viewing angle to be between 0-2PI
-        if player_angle < 0. {
-            player_angle = 2. * PI;
-        }
-        if player_angle > 2. * PI {
-            player_angle = 0.;
-        }
-
-        // moves the player forward or backwards
-        // with super basic collision detection
-        if is_key_down(KeyCode::W) {
-            let x_move = player_angle.cos() * delta_time * PLAYER_MOVE_SPEED;
-            let y_move = player_angle.sin() * delta_time * PLAYER_MOVE_SPEED;
+        let mouse = mouse_position();
+        let mouse_dx = mouse.0 - last_mouse.0;
+        let mouse_dy = mouse.1 - last_mouse.1;
+        last_mouse = mouse;
 
-            let target_x = player.0 + x_move;
-            let target_y = player.1 + y_move;
+        if menu.open {
+            if menu.update(&mut controls) {
+                controls.save(CONTROLS_FILE).ok();
+            }
+        } else {
+            // updates the players viewing angle
+            if controls.is_down(Action::TurnLeft) {
+                player_angle -= PLAYER_TURN_SPEED * delta_time;
+            }
+            if controls.is_down(Action::TurnRight) {
+                player_angle += PLAYER_TURN_SPEED * delta_time;
+            }
+            player_angle += controls.mouse_turn(mouse_dx);
 
-            if engine.map.get_cell(target_x as usize, player.1 as usize).unwrap() == 0 {//[(player.1 as usize) * engine.map_size.0 + (target_x as usize)] == 0 {
-                player.0 = target_x;
-            } else {
-                player.0 = target_x.floor() + if player.0 < target_x { -0.01 } else { 1.01 };
+            // updates how far up or down the player is looking
+            if controls.is_down(Action::LookUp) {
+                player_pitch += PLAYER_TURN_SPEED * delta_time;
             }
+            if controls.is_down(Action::LookDown) {
+                player_pitch -= PLAYER_TURN_SPEED * delta_time;
+            }
+            player_pitch = (player_pitch + controls.mouse_pitch(mouse_dy)).clamp(-MAX_PITCH, MAX_PITCH);
+
+            // limits the viewing angle to be between 0-2PI
+            player_angle = correct_angle(player_angle);
 
-            if engine.map.get_cell(player.0 as usize, target_y as usize).unwrap() == 0 {//[(target_y as usize) * engine.map_size.0 + (player.0 as usize)] == 0 {
-                player.1 = target_y;
-            } else {
-                player.1 = target_y.floor() + if player.1 < target_y { -0.01 } else { 1.01 };
+            // gets the direction the player wants to move in relative to where they are looking
+            let mut forward = 0.;
+            let mut strafe = 0.;
+            if controls.is_down(Action::Forward) {
+                forward += 1.;
+            }
+            if controls.is_down(Action::Backward) {
+                forward -= 1.;
+            }
+            if controls.is_down(Action::StrafeRight) {
+                strafe += 1.;
+            }
+            if controls.is_down(Action::StrafeLeft) {
+                strafe -= 1.;
             }
-        }
-        if is_key_down(KeyCode::S) {
-            let x_move = player_angle.cos() * delta_time * PLAYER_MOVE_SPEED;
-            let y_move = player_angle.sin() * delta_time * PLAYER_MOVE_SPEED;
 
-            let target_x = player.0 - x_move;
-            let target_y = player.1 - y_move;
+            if forward != 0. || strafe != 0. {
+                let speed = if body.crouching {
+                    PLAYER_MOVE_SPEED * PLAYER_CROUCH_SPEED
+                } else if controls.is_down(Action::Sprint) {
+                    PLAYER_MOVE_SPEED * PLAYER_SPRINT_MULTIPLIER
+                } else {
+                    PLAYER_MOVE_SPEED
+                };
+
+                // normalizes the movement so moving diagonally isnt faster
+                let length = f32::sqrt(forward * forward + strafe * strafe);
+                let x_move = (player_angle.cos() * forward - player_angle.sin() * strafe) / length * speed * delta_time;
+                let y_move = (player_angle.sin() * forward + player_angle.cos() * strafe) / length * speed * delta_time;
 
-            if engine.map.get_cell(target_x as usize, player.1 as usize).unwrap() == 0 {
-                player.0 = target_x;
-            } else {
-                player.0 = target_x.floor() + if player.0 < target_x { -0.01 } else { 1.01 };
+                move_player(&engine.map, &mut player, body.z, body.height(), x_move, y_move);
+
+                // walking into a portal moves and turns the player the same way it does the rays
+                let (x, y) = (player.0 as usize, player.1 as usize);
+                let (layer, _, _) = engine.map.find_storey(x, y, body.z);
+                if let Some(portal) = engine.map.storeys[layer].get_portal(x, y) {
+                    let transform = portal.transform((x, y));
+                    player = transform.apply_point(player);
+                    player_angle = correct_angle(player_angle + transform.angle());
+                }
             }
+        }
 
-            if engine.map.get_cell(player.0 as usize, target_y as usize).unwrap() == 0 {
-                player.1 = target_y;
-            } else {
-                player.1 = target_y.floor() + if player.1 < target_y { -0.01 } else { 1.01 };
+        // slides the doors open while the player is close to them and closed again once they leave
+        for (x, y) in doors {
+            let near = f32::hypot(x as f32 + 0.5 - player.0, y as f32 + 0.5 - player.1) < DOOR_OPEN_DISTANCE;
+            if let Some(CellShape::Door { open, .. }) = engine.map.storeys[0].shapes.get_mut(&(x, y)) {
+                *open = if near {
+                    (*open + DOOR_SPEED * delta_time).min(1.)
+                } else {
+                    (*open - DOOR_SPEED * delta_time).max(0.)
+                };
             }
         }
 
+        // updates the players height, the jump/crouch input is ignored while the menu is open
+        let (_, floor_height, ceil_height) = engine.map.find_storey(player.0 as usize, player.1 as usize, body.z);
+        body.update(
+            !menu.open && controls.is_down(Action::Jump),
+            !menu.open && controls.is_down(Action::Crouch),
+            floor_height,
+            ceil_height,
+            delta_time,
+        );
+
+        // the camera height is measured from the bottom of the storey the player is on
+        let (layer, _, _) = engine.map.find_storey(player.0 as usize, player.1 as usize, body.z);
+        let camera_height = body.camera_height() - layer as f32 * STOREY_HEIGHT;
+
+        // shears the view up or down instead of actually rotating the camera,
+        // this moves the horizon but keeps walls vertical
+        let horizon = screen_height() / 2. + player_pitch.tan() * plane_dist;
+
+        // the sky moves with the horizon, anything above it is filled with the skys color
+        let sky_start_y = horizon - screen_height() / 2.;
+        if sky_start_y > 0. {
+            draw_rectangle(0., 0., screen_width(), sky_start_y, sky_color);
+        }
+
         let sky_start_x = ((correct_angle(-player_angle*5.5) / (2. * PI)) * sky_width) - sky_width;
-        draw_texture_ex(sky, sky_start_x, 0., WHITE, DrawTextureParams { dest_size: Some(vec2(sky_width, sky_height)), ..Default::default() });
+        draw_texture_ex(sky, sky_start_x, sky_start_y, WHITE, DrawTextureParams { dest_size: Some(vec2(sky_width, sky_height)), ..Default::default() });
 
         if sky_start_x > 0. {
-            draw_texture_ex(sky, sky_start_x - sky_width, 0., WHITE, DrawTextureParams { dest_size: Some(vec2(sky_width, sky_height)), ..Default::default() });
+            draw_texture_ex(sky, sky_start_x - sky_width, sky_start_y, WHITE, DrawTextureParams { dest_size: Some(vec2(sky_width, sky_height)), ..Default::default() });
         } else {
-            draw_texture_ex(sky, sky_start_x + sky_width, 0., WHITE, DrawTextureParams { dest_size: Some(vec2(sky_width, sky_height)), ..Default::default() });
+            draw_texture_ex(sky, sky_start_x + sky_width, sky_start_y, WHITE, DrawTextureParams { dest_size: Some(vec2(sky_width, sky_height)), ..Default::default() });
         }
 
         let mut angles = Vec::new();
         for i in 0..(total_num_of_cols as u32) {
             // gets the current angle using the size of each column, the total screen size, and trigonometry
-            let dist_from_middle = ((total_num_of_cols / 2.) - i as f32) * WIDTH_3D as f32;
+            let dist_from_middle = ((total_num_of_cols / 2.) - i as f32) * WIDTH_3D;
             let angle_dist_from_plane = (dist_from_middle.powi(2) + plane_dist.powi(2)).sqrt();
             let mut angle = (dist_from_middle / angle_dist_from_plane).asin();
 
@@ -290,123 +591,139 @@ async fn main() {
             angles.push(angle);
         }
 
-        // casts all the rays and stores them in a hashmap along with their column
-        let rays = engine.cast_rays_multi(player, angles.clone(), VIEW_DISTANCE);
+        // casts all the rays and stores everything that can be seen along with their column
+        let rays = engine.cast_rays_spans_multi_on_layer(layer, player, angles.clone(), VIEW_DISTANCE, camera_height);
 
-        for (i, ray_data) in rays {
-            let angle = ray_data.ray_angle;
+        for (i, spans) in rays {
+            let angle = angles[i];
 
-            // uses the rays direction and length to calculate where the collision occured
-            let end_point = (
-                ray_data.ray_direction.0 * ray_data.ray_length + ray_data.ray_position.0,
-                ray_data.ray_direction.1 * ray_data.ray_length + ray_data.ray_position.1,
-            );
+            // removes the fisheye effect
+            let rel_angle = correct_angle(player_angle - angle);
 
-            // checks if the ray collided with
-            // anything and if so, get its texture
-            // otherwise uses first texture
-            match ray_data.hit_val {
-                Some(val) => texture = textures.get(&val).unwrap().0,
-                None => texture = textures.get(&0).unwrap().0
-            }
+            // gets the row on the screen that a slope (height over distance along the ray) shows up at
+            let slope_to_y = |slope: f32| horizon - slope * plane_dist / rel_angle.cos();
 
-            // uses how far into a cell the ray collided
-            // to decide where to sample the texture from,
-            // also flips the textures on certain walls so
-            // directional textures work on any surface
-
-            // gets the x or y coord depending on if the collision was on the y-axis or not
-            let coord = if ray_data.collided_vertical {
-                end_point.1
-            } else {
-                end_point.0
-            };
-
-            // checks if the texture will need to be flipped depending on what direction the ray is facing
-            let flip_check = if ray_data.collided_vertical {
-                ray_data.ray_direction.0 < 0.
-            } else {
-                ray_data.ray_direction.1 > 0.
-            };
-
-            // gets the column that the sub image will occupy
-            let texture_col = if flip_check {
-                // gets the column from the back if the texture needs to be flipped
-                (BLOCK_SIZE - 1.) - (coord % coord.floor() * BLOCK_SIZE).floor()
-            } else {
-                // gets column from front otherwise
-                (coord % coord.floor() * BLOCK_SIZE).floor()
-            };
-
-            // builds a 1 pixel column for the texture sampling later
-            let sub_image = Rect::new(texture_col, 0., 1., BLOCK_SIZE);
-
-            // makes the texture drawn darker the farther away it is
-            let shade = 1. - ray_data.ray_length / VIEW_DISTANCE;
-            let color = Color::new(1. * shade, 1. * shade, 1. * shade, 1.);
+            // each span only covers the part of the column that can be seen,
+            // so they can be drawn in any order without overlapping
+            for ray_data in spans {
+                let top_y = slope_to_y(ray_data.view_slopes.1).max(0.);
+                let bottom_y = slope_to_y(ray_data.view_slopes.0).min(screen_height());
+                if top_y >= bottom_y {
+                    continue;
+                }
 
-            // removes the fisheye effect
-            let rel_angle = correct_angle(player_angle - angle);
-            let distance = ray_data.ray_length * f32::cos(rel_angle);
-
-            let line_hight = plane_dist / distance;
-            let line_offset = (screen_height() as f32 / 2.) - line_hight / 2.;
-            draw_texture_ex(
-                texture,
-                i as f32 * WIDTH_3D,
-                line_offset,
-                color,
-                DrawTextureParams {
-                    dest_size: Some(vec2(WIDTH_3D as f32, line_hight)),
-                    source: Some(sub_image),
-                    ..Default::default()
-                },
-            );
-
-            // draws the floor for the current column
-            for y in (line_offset as u32 + line_hight as u32)..(screen_height() as u32) {
-                let dy = (y - (HEIGHT as u32 / 2)) as f32;
-
-                // precalculate as much of the equations as possible to save on number of divisions
-                let f = ar * 2. / (dy * rel_angle.cos());
-                
-                let tx = player.0 + angle.cos() * f;
-                let ty = player.1 + angle.sin() * f;
-
-                let floor_col = if let Some(v) = engine.map.get_floor(tx as usize, ty as usize) {
-                    match v {
-                        1 => bricks_image.get_pixel(((tx % 1.) * 64.) as u32, ((ty % 1.) * 64.) as u32),
-                        2 => blackstone_image.get_pixel(((tx % 1.) * 64.) as u32, ((ty % 1.) * 64.) as u32),
-                        3 => plank_image.get_pixel(((tx % 1.) * 64.) as u32, ((ty % 1.) * 64.) as u32),
-                        _ => plank_image.get_pixel(((tx % 1.) * 64.) as u32, ((ty % 1.) * 64.) as u32)
+                let (cell_x, cell_y) = (ray_data.hit_cell.0 as usize, ray_data.hit_cell.1 as usize);
+                let storey = &engine.map.storeys[ray_data.layer];
+
+                if ray_data.span_kind == SpanKind::Floor || ray_data.span_kind == SpanKind::Ceiling {
+                    let height = ray_data.wall_span.0;
+
+                    // floors use the floor texture, unless they are the top of a wall
+                    let val = match (ray_data.span_kind, ray_data.hit_val) {
+                        (SpanKind::Ceiling, _) => storey.get_ceil(cell_x, cell_y),
+                        (_, Some(0)) | (_, None) => storey.get_floor(cell_x, cell_y),
+                        (_, val) => val,
+                    };
+
+                    for y in (top_y as u32)..(bottom_y as u32) {
+                        let dy = y as f32 - horizon;
+
+                        // distance along the ray to the point on the floor/ceiling this pixel shows
+                        let f = ((camera_height - height) * plane_dist / (dy * rel_angle.cos())).min(VIEW_DISTANCE);
+                        if f.is_nan() || f < 0. {
+                            continue;
+                        }
+
+                        // the point is found along the last part of the ray, after any portals and mirrors it went through
+                        let tx = ray_data.local_origin.0 + ray_data.local_direction.0 * f;
+                        let ty = ray_data.local_origin.1 + ray_data.local_direction.1 * f;
+
+                        let col = surface_pixel(val, tx, ty);
+
+                        // adds shading to the floor/ceiling depending on how close it is to the horizon
+                        let shade = (dy.abs() / (screen_height() / 2.) + 0.3).clamp(0., 1.);
+                        let tint = mirror_tint(ray_data.bounces);
+                        let col = Color::new(col.r * shade * tint.r, col.g * shade * tint.g, col.b * shade * tint.b, 1.);
+
+                        floor_image.set_pixel(i as u32, y, col);
                     }
+
+                    continue;
+                }
+
+                // walls use their own texture and steps use the texture of the floor/ceiling they lead to
+                let texture_id = match ray_data.span_kind {
+                    SpanKind::FloorStep => storey.get_floor(cell_x, cell_y),
+                    SpanKind::CeilingStep => storey.get_ceil(cell_x, cell_y),
+                    _ => ray_data.hit_val,
+                };
+                let texture = textures.get(&texture_id.unwrap_or(0)).unwrap_or(&textures[&3]).0;
+
+                // uses how far along the wall the ray collided
+                // to decide where to sample the texture from,
+                // also flips the textures on certain walls so
+                // directional textures work on any surface
+
+                // checks if the texture will need to be flipped depending on what direction the ray is facing
+                // round and diagonal walls already have their textures running the right way
+                let flip_check = if ray_data.normal.1 == 0. {
+                    ray_data.normal.0 > 0.
+                } else if ray_data.normal.0 == 0. {
+                    ray_data.normal.1 < 0.
                 } else {
-                    plank_image.get_pixel(((tx % 1.) * 64.) as u32, ((ty % 1.) * 64.) as u32)
+                    false
                 };
 
-                let ceil_col = if let Some(v) = engine.map.get_ceil(tx as usize, ty as usize) {
-                    match v {
-                        1 => bricks_image.get_pixel(((tx % 1.) * 64.) as u32, ((ty % 1.) * 64.) as u32),
-                        2 => blackstone_image.get_pixel(((tx % 1.) * 64.) as u32, ((ty % 1.) * 64.) as u32),
-                        3 => plank_image.get_pixel(((tx % 1.) * 64.) as u32, ((ty % 1.) * 64.) as u32),
-                        _ => plank_image.get_pixel(((tx % 1.) * 64.) as u32, ((ty % 1.) * 64.) as u32)
-                    }
+                // gets the column that the sub image will occupy
+                let texture_col = if flip_check {
+                    // gets the column from the back if the texture needs to be flipped
+                    (BLOCK_SIZE - 1.) - (ray_data.texture_u * BLOCK_SIZE).floor()
                 } else {
-                    plank_image.get_pixel(((tx % 1.) * 64.) as u32, ((ty % 1.) * 64.) as u32)
+                    // gets column from front otherwise
+                    (ray_data.texture_u * BLOCK_SIZE).floor()
                 };
 
-                // adds shading to the floor/ceiling depending on how close it is to the center of the screen
-                let shade = ((y as f32 - screen_height() / 2.) / (screen_height() / 2.) + 0.3).clamp(0., 1.);
-                let floor_col = Color::new(floor_col.r * shade, floor_col.g * shade, floor_col.b * shade, 1.);
-                let ceil_col = Color::new(ceil_col.r * shade, ceil_col.g * shade, ceil_col.b * shade, 1.);
-
-                // draws the found floor color to the screen
-                floor_image.set_pixel(i as u32, y, floor_col);
-
-                // only draws the ceiling if there was a texture to draw
-                // otherwise it is left blank for the sky to show
-                if engine.map.get_ceil(tx as usize, ty as usize).unwrap() != 0 {
-                    floor_image.set_pixel(i as u32, screen_height() as u32 - y, ceil_col);
+                // makes the texture drawn darker the farther away it is
+                let shade = 1. - ray_data.ray_length / VIEW_DISTANCE;
+                let tint = mirror_tint(ray_data.bounces);
+                let color = Color::new(tint.r * shade, tint.g * shade, tint.b * shade, 1.);
+
+                let distance = ray_data.ray_length * f32::cos(rel_angle);
+
+                // converts between heights on the wall and rows on the screen
+                let z_to_y = |z: f32| horizon - (z - camera_height) * plane_dist / distance;
+                let y_to_z = |y: f32| camera_height + (horizon - y) * distance / plane_dist;
+
+                // draws the visible part of the wall one unit at a time so the texture repeats on tall walls,
+                // the texture starts at the bottom of the wall
+                let base = ray_data.wall_span.0;
+                let top = y_to_z(top_y);
+                let mut z = y_to_z(bottom_y);
+                while z < top {
+                    let unit = (z - base).floor();
+                    let z_end = (base + unit + 1.).min(top);
+
+                    // builds a 1 pixel column for the part of the texture this piece of wall uses
+                    let sub_image = Rect::new(
+                        texture_col,
+                        (unit + 1. - (z_end - base)) * BLOCK_SIZE,
+                        1.,
+                        (z_end - z) * BLOCK_SIZE,
+                    );
+
+                    draw_texture_ex(
+                        texture,
+                        i as f32 * WIDTH_3D,
+                        z_to_y(z_end),
+                        color,
+                        DrawTextureParams {
+                            dest_size: Some(vec2(WIDTH_3D, (z_end - z) * plane_dist / distance)),
+                            source: Some(sub_image),
+                            ..Default::default()
+                        },
+                    );
+
+                    z = z_end;
                 }
             }
         }
@@ -428,10 +745,63 @@ async fn main() {
             WHITE,
         );
 
+        if menu.open {
+            menu.draw(&controls);
+        }
+
         next_frame().await;
     }
 }
 
+/// moves the player by the given amount with super basic collision
+/// detection, each axis is checked separately so the player slides along walls.
+/// `feet` is the height of the bottom of the player and `height` is how tall they are,
+/// steps too high to walk up and ceilings too low to fit under block the player the same as walls.
+/// the walls that block the player are the ones in the storey they would be standing in,
+/// doors only let the player through once they are almost all the way open. the player can
+/// walk around pillars and up to diagonal walls from the right of the line from their start to end
+fn move_player(map: &Storeys<World>, player: &mut (f32, f32), feet: f32, height: f32, x_move: f32, y_move: f32) {
+    let target_x = player.0 + x_move;
+    let target_y = player.1 + y_move;
+
+    let is_open = |x: f32, y: f32| {
+        let (cell_x, cell_y) = (x as usize, y as usize);
+        let (u, v) = (x - x.floor(), y - y.floor());
+        let (layer, floor, ceil) = map.find_storey(cell_x, cell_y, feet + MAX_STEP_HEIGHT);
+        let passable = match map.storeys[layer].get_shape(cell_x, cell_y) {
+            // portals can always be walked into, the player is moved out of them right away
+            _ if map.storeys[layer].get_portal(cell_x, cell_y).is_some() => true,
+            CellShape::Door { open, .. } => open > 0.9,
+            CellShape::Segment { start, end } => (end.0 - start.0) * (v - start.1) - (end.1 - start.1) * (u - start.0) > 0.,
+            CellShape::Circle { center, radius } => f32::hypot(u - center.0, v - center.1) > radius,
+            _ => map.storeys[layer].get_cell(cell_x, cell_y).unwrap() == 0,
+        };
+        passable
+            && floor <= feet + MAX_STEP_HEIGHT
+            && ceil >= floor.max(feet) + height
+    };
+
+    // only snaps to the edge of the cell when moving into a new one,
+    // walls inside of a cell just stop the player where they are
+    if is_open(target_x, player.1) {
+        player.0 = target_x;
+    } else if target_x.floor() != player.0.floor() {
+        player.0 = target_x.floor() + if player.0 < target_x { -0.01 } else { 1.01 };
+    }
+
+    if is_open(player.0, target_y) {
+        player.1 = target_y;
+    } else if target_y.floor() != player.1.floor() {
+        player.1 = target_y.floor() + if player.1 < target_y { -0.01 } else { 1.01 };
+    }
+}
+
+/// things seen in mirrors get a little bluer and darker with every bounce
+fn mirror_tint(bounces: u32) -> Color {
+    let fade = 0.85_f32.powi(bounces as i32);
+    Color::new(fade * 0.9_f32.powi(bounces as i32), fade * 0.95_f32.powi(bounces as i32), fade, 1.)
+}
+
 fn get_average_texture_color(texture: &Texture2D) -> Color {
     let width = texture.width() as u32;
     let height = texture.height() as u32;
@@ -0,0 +1,66 @@
+use crate::shapes::ShapeHit;
+use crate::{CellShape, DdaWalker, Float, Map, RayCastEngine, RayData};
+
+/// a straight piece of the path a ray took, rays are split into
+/// segments where they bounce off mirrors and go through portals
+#[derive(Default, Clone, Copy, Debug)]
+pub struct RaySegment<F = f32> {
+    pub start: (F, F),
+    pub end: (F, F),
+
+    /// the length of the whole ray at the start and end of the segment
+    pub start_length: F,
+    pub end_length: F,
+}
+
+impl<F: Float> RaySegment<F> {
+    /// the segment of a ray going from `origin` in `dir` between the two lengths
+    pub(crate) fn new(origin: (F, F), dir: (F, F), start_length: F, end_length: F) -> Self {
+        Self {
+            start: (origin.0 + dir.0 * start_length, origin.1 + dir.1 * start_length),
+            end: (origin.0 + dir.0 * end_length, origin.1 + dir.1 * end_length),
+            start_length,
+            end_length,
+        }
+    }
+}
+
+impl<T: Map + std::marker::Sync, F: Float> RayCastEngine<T, F> {
+    /// same as `cast_ray` but also returns every segment of the path the ray took as it
+    /// bounced off mirrors and went through portals, ordered from the start of the ray
+    pub fn cast_ray_path(&self, pos: (F, F), angle: F, max_distance: F) -> (Vec<RaySegment<F>>, RayData<F>) {
+        let mut segments = Vec::new();
+        let ray = self.trace_ray(pos, angle, max_distance, Some(&mut segments));
+        (segments, ray)
+    }
+}
+
+impl<F: Float> DdaWalker<F> {
+    /// reflects the ray off of the mirror it hit, the ray carries on from the hit point
+    /// with the same length so distances along it stay correct
+    pub(crate) fn bounce(&mut self, hit: &ShapeHit<F>, shape: CellShape) {
+        let dot = self.dir.0 * hit.normal.0 + self.dir.1 * hit.normal.1;
+        let twice = dot + dot;
+        let dir = (self.dir.0 - twice * hit.normal.0, self.dir.1 - twice * hit.normal.1);
+
+        // keeps the direction normalized so rounding doesnt build up over many bounces
+        let length = (dir.0 * dir.0 + dir.1 * dir.1).sqrt();
+        let dir = (dir.0 / length, dir.1 / length);
+
+        let point = (self.origin.0 + self.dir.0 * hit.distance, self.origin.1 + self.dir.1 * hit.distance);
+        let origin = (point.0 - dir.0 * hit.distance, point.1 - dir.1 * hit.distance);
+
+        // rays bouncing off the side of a full cell go back into the cell they came from
+        let cell = if shape == CellShape::Block {
+            if hit.collided_vertical {
+                (self.cell.0 - self.step.0, self.cell.1)
+            } else {
+                (self.cell.0, self.cell.1 - self.step.1)
+            }
+        } else {
+            self.cell
+        };
+
+        *self = Self::new_in_cell(origin, dir, cell, self.transform);
+    }
+}
@@ -0,0 +1,171 @@
+use crate::{cast_multi, Map, RayCastEngine, RayData};
+
+/// the shape of an object that isnt on the grid, in world space
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ObjectShape {
+    Circle { center: (f32, f32), radius: f32 },
+
+    /// a box lined up with the axes between its smallest and largest corners
+    Aabb { min: (f32, f32), max: (f32, f32) },
+}
+
+/// something off the grid that rays can hit, like an entity or a sprite
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Object {
+    /// picked by the caller to tell which object was hit
+    pub id: usize,
+    pub shape: ObjectShape,
+}
+
+/// where a ray hit an object
+#[derive(Default, Clone, Copy, Debug)]
+pub struct ObjectHit {
+    /// the id of the object that was hit
+    pub id: usize,
+
+    /// the length of the ray from the starting position to when it collided
+    pub ray_length: f32,
+
+    /// the point the ray collided at, in the space the ray was in
+    /// after going through any portals and mirrors
+    pub point: (f32, f32),
+
+    /// a unit vector pointing out of the object where the ray collided
+    pub normal: (f32, f32),
+}
+
+/// whatever a ray hit first out of the map and a set of objects
+#[derive(Clone, Copy)]
+pub enum RayHit {
+    /// the ray got to a wall first or didnt hit anything, the same as `cast_ray` would return
+    Wall(RayData),
+    Object(ObjectHit),
+}
+
+impl ObjectShape {
+    /// the smallest and largest corners of the box around the shape
+    pub fn bounds(&self) -> ((f32, f32), (f32, f32)) {
+        match *self {
+            ObjectShape::Circle { center, radius } => ((center.0 - radius, center.1 - radius), (center.0 + radius, center.1 + radius)),
+            ObjectShape::Aabb { min, max } => (min, max),
+        }
+    }
+
+    /// intersects a ray with the shape between the lengths in `span`, returns the length of the
+    /// ray and the normal where it goes into the shape. rays starting inside the shape never hit it
+    pub fn intersect(&self, pos: (f32, f32), dir: (f32, f32), span: (f32, f32)) -> Option<(f32, (f32, f32))> {
+        let (distance, normal) = match *self {
+            ObjectShape::Circle { center, radius } => {
+                let from_center = (pos.0 - center.0, pos.1 - center.1);
+                if from_center.0 * from_center.0 + from_center.1 * from_center.1 < radius * radius {
+                    return None;
+                }
+
+                // goes through the point on the ray closest to the center so it stays precise far away
+                let closest = -(from_center.0 * dir.0 + from_center.1 * dir.1);
+                let offset = (from_center.0 + dir.0 * closest, from_center.1 + dir.1 * closest);
+                let discriminant = radius * radius - (offset.0 * offset.0 + offset.1 * offset.1);
+                if discriminant < 0. {
+                    return None;
+                }
+
+                let distance = closest - discriminant.sqrt();
+                let hit = (from_center.0 + dir.0 * distance, from_center.1 + dir.1 * distance);
+                let length = f32::sqrt(hit.0 * hit.0 + hit.1 * hit.1);
+                (distance, (hit.0 / length, hit.1 / length))
+            }
+            ObjectShape::Aabb { min, max } => {
+                // the lengths where the ray is between the sides along each axis
+                let slab = |p: f32, d: f32, low: f32, high: f32| {
+                    if d == 0. {
+                        if p < low || p > high {
+                            (f32::INFINITY, f32::NEG_INFINITY)
+                        } else {
+                            (f32::NEG_INFINITY, f32::INFINITY)
+                        }
+                    } else {
+                        let (a, b) = ((low - p) / d, (high - p) / d);
+                        (a.min(b), a.max(b))
+                    }
+                };
+                let x = slab(pos.0, dir.0, min.0, max.0);
+                let y = slab(pos.1, dir.1, min.1, max.1);
+
+                let enter = x.0.max(y.0);
+                let exit = x.1.min(y.1);
+                if enter > exit || enter < 0. {
+                    return None;
+                }
+
+                // the side the ray goes in through is on whichever axis it entered last
+                let normal = if x.0 > y.0 {
+                    (-dir.0.signum(), 0.)
+                } else {
+                    (0., -dir.1.signum())
+                };
+                (enter, normal)
+            }
+        };
+
+        if distance < span.0 || distance > span.1 {
+            return None;
+        }
+        Some((distance, normal))
+    }
+}
+
+impl<T: Map + std::marker::Sync> RayCastEngine<T> {
+    /// casts a single ray against the map and a list of objects and returns whichever it hits first.
+    /// objects are hit along the whole path of the ray, even after it goes through portals and mirrors
+    pub fn cast_ray_objects(&self, pos: (f32, f32), angle: f32, max_distance: f32, objects: &[Object]) -> RayHit {
+        self.cast_ray_with_objects(pos, angle, max_distance, |start, dir, length| {
+            objects
+                .iter()
+                .filter_map(|object| object.shape.intersect(start, dir, (0., length)).map(|(distance, normal)| (object.id, distance, normal)))
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+        })
+    }
+
+    /// same as `cast_rays_multi` but uses `cast_ray_objects` for each ray
+    pub fn cast_rays_multi_objects(&self, pos: (f32, f32), angles: Vec<f32>, max_distance: f32, objects: &[Object]) -> Vec<(usize, RayHit)> {
+        cast_multi(&angles, |angle| self.cast_ray_objects(pos, *angle, max_distance, objects))
+    }
+
+    /// casts a ray against the map and looks for objects along each straight part of its path with
+    /// `nearest`, which is given the start, direction and length of a part and returns the id, length
+    /// along the part and normal of the nearest object it hits
+    pub(crate) fn cast_ray_with_objects(
+        &self,
+        pos: (f32, f32),
+        angle: f32,
+        max_distance: f32,
+        nearest: impl Fn((f32, f32), (f32, f32), f32) -> Option<(usize, f32, (f32, f32))>,
+    ) -> RayHit {
+        let (segments, ray) = self.cast_ray_path(pos, angle, max_distance);
+
+        // the ray only goes as far as max distance when it doesnt hit a wall
+        let end = if ray.hit_val.is_some() { ray.ray_length } else { max_distance };
+
+        for (i, segment) in segments.iter().enumerate() {
+            let length = segment.end_length - segment.start_length;
+            if length <= 0. {
+                continue;
+            }
+
+            // the last segment keeps going to max distance if the ray didnt hit anything
+            let dir = ((segment.end.0 - segment.start.0) / length, (segment.end.1 - segment.start.1) / length);
+            let span_end = if i == segments.len() - 1 { end - segment.start_length } else { length };
+
+            if let Some((id, distance, normal)) = nearest(segment.start, dir, span_end) {
+                return RayHit::Object(ObjectHit {
+                    id,
+                    ray_length: segment.start_length + distance,
+                    point: (segment.start.0 + dir.0 * distance, segment.start.1 + dir.1 * distance),
+                    normal,
+                });
+            }
+        }
+
+        RayHit::Wall(ray)
+    }
+}
@@ -0,0 +1,141 @@
+use crate::{box_lengths, DdaWalker, Map, PortalTransform, RayCastEngine, Reading, Scan};
+
+/// how close the end of a beam has to be to the edge of a cell in grid space for its hit to be on the edge,
+/// the lengths of beams from a scan come back in world space and might not line up with the edges exactly
+const EDGE_TOLERANCE: f32 = 1e-4;
+
+/// a map built up from range scans, each cell holds the log odds of it being occupied. beams
+/// make the cells they go through more likely to be free and the cell they hit more likely to
+/// be occupied. cells start at 0, which is even odds, and are walls once they are above `threshold`
+pub struct OccupancyGrid {
+    size: (usize, usize),
+    log_odds: Vec<f32>,
+
+    /// how much a beam hitting a cell adds to its log odds
+    pub hit: f32,
+
+    /// how much a beam going through a cell adds to its log odds, this should be negative
+    pub miss: f32,
+
+    /// the smallest and largest log odds a cell can have, so cells can still change after being seen many times
+    pub limits: (f32, f32),
+
+    /// the log odds a cell has to be above to be a wall when it is raycast
+    pub threshold: f32,
+}
+
+impl OccupancyGrid {
+    /// creates a grid where nothing is known yet
+    pub fn new(size: (usize, usize)) -> Self {
+        Self {
+            size,
+            log_odds: vec![0.; size.0 * size.1],
+            hit: 0.85,
+            miss: -0.4,
+            limits: (-4., 4.),
+            threshold: 0.,
+        }
+    }
+
+    /// the log odds of a cell being occupied, or None if it is outside of the grid
+    pub fn log_odds(&self, x: usize, y: usize) -> Option<f32> {
+        if x >= self.size.0 || y >= self.size.1 {
+            return None;
+        }
+        Some(self.log_odds[y * self.size.0 + x])
+    }
+
+    /// the chance from 0 to 1 of a cell being occupied, or None if it is outside of the grid
+    pub fn probability(&self, x: usize, y: usize) -> Option<f32> {
+        self.log_odds(x, y).map(|log_odds| 1. - 1. / (1. + log_odds.exp()))
+    }
+
+    /// checks if a cell is inside of the grid
+    fn contains(&self, cell: (i32, i32)) -> bool {
+        cell.0 >= 0 && cell.1 >= 0 && (cell.0 as usize) < self.size.0 && (cell.1 as usize) < self.size.1
+    }
+
+    /// adds to the log odds of a cell, cells outside of the grid are left alone
+    fn update(&mut self, cell: (i32, i32), change: f32) {
+        if !self.contains(cell) {
+            return;
+        }
+        let log_odds = &mut self.log_odds[cell.1 as usize * self.size.0 + cell.0 as usize];
+        *log_odds = (*log_odds + change).clamp(self.limits.0, self.limits.1);
+    }
+}
+
+impl Map for OccupancyGrid {
+    fn get_cell(&self, x: usize, y: usize) -> Option<u32> {
+        self.log_odds(x, y).map(|log_odds| (log_odds > self.threshold) as u32)
+    }
+
+    fn get_size(&self) -> (usize, usize) {
+        self.size
+    }
+}
+
+impl RayCastEngine<OccupancyGrid> {
+    /// adds a beam going from `pos` at `angle` that measured `range` to the grid, walking it through the
+    /// cells the same way `cast_ray` does. every cell before the end of the beam is more likely to be free
+    /// and if `hit` the cell at the end is more likely to be occupied. beams that didnt hit anything should
+    /// be given their max range. a distance field has to be built again after changing the grid.
+    /// beams with a position, angle or range that isnt finite cant be walked so they are left out, and
+    /// only the part of a beam inside the grid is walked so long ranges dont take any longer
+    pub fn insert_beam(&mut self, pos: (f32, f32), angle: f32, range: f32, hit: bool) {
+        let grid = self.grid_ray(pos, angle);
+        let range = grid.length(range);
+        let finite = [range, grid.pos.0, grid.pos.1, grid.dir.0, grid.dir.1].iter().all(|value| value.is_finite());
+        if !finite {
+            return;
+        }
+
+        let size = (self.map.size.0 as f32, self.map.size.1 as f32);
+        let Some((enter, _)) = box_lengths(grid.pos, grid.dir, (0., 0.), size) else {
+            return;
+        };
+
+        // beams starting outside of the grid are walked from where they enter it, the lengths
+        // of the walker are from there so `start` is added back on to compare them to the range
+        let (mut walker, start) = if enter > 0. {
+            let entry = (grid.pos.0 + grid.dir.0 * enter, grid.pos.1 + grid.dir.1 * enter);
+            let cell = (
+                (entry.0.floor() as i32).clamp(0, self.map.size.0 as i32 - 1),
+                (entry.1.floor() as i32).clamp(0, self.map.size.1 as i32 - 1),
+            );
+            (DdaWalker::new_in_cell(entry, grid.dir, cell, PortalTransform::default()), enter)
+        } else {
+            (DdaWalker::new(grid.pos, grid.dir), 0.)
+        };
+
+        loop {
+            // hits right on the edge of a cell belong to the cell the beam is going into
+            if start + walker.exit_length() > range + EDGE_TOLERANCE {
+                if hit {
+                    self.map.update(walker.cell, self.map.hit);
+                }
+                return;
+            }
+
+            self.map.update(walker.cell, self.map.miss);
+            walker.step();
+
+            // the walker never comes back into the grid once it has left
+            if !self.map.contains(walker.cell) {
+                return;
+            }
+        }
+    }
+
+    /// adds every beam of a scan to the grid with `insert_beam`. beams that didnt hit anything
+    /// before the max range free the cells up to it and beams that didnt come back are left out
+    pub fn insert_scan(&mut self, scan: &Scan) {
+        for (angle, reading) in scan.angles.iter().zip(&scan.ranges) {
+            match reading {
+                Reading::Hit(range) => self.insert_beam(scan.pos, scan.heading + angle, *range, true),
+                Reading::MaxRange => self.insert_beam(scan.pos, scan.heading + angle, scan.max_range, false),
+                Reading::Dropout => {}
+            }
+        }
+    }
+}
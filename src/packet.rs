@@ -0,0 +1,164 @@
+use std::array;
+
+use crate::simd::{F32x4, I32x4};
+use crate::{DdaWalker, Map, RayCastEngine, RayData};
+
+/// how many rays `cast_rays_multi` casts together, neighbouring columns
+/// on the screen go through nearly the same cells so they step well together
+pub(crate) const PACKET_SIZE: usize = 8;
+
+/// four rays being stepped together, holding the parts of their walkers that change while stepping
+struct Quad {
+    cell: (I32x4, I32x4),
+    step: (I32x4, I32x4),
+    ray_unit_step_size: (F32x4, F32x4),
+    first_length: (F32x4, F32x4),
+    crossed: (I32x4, I32x4),
+    ray_length_1d: (F32x4, F32x4),
+    max_length: F32x4,
+
+    /// the rays that are still stepping
+    active: I32x4,
+
+    /// the length of each ray and if the edge was vertical when it went into the cell it is in
+    entered_length: F32x4,
+    entered_vertical: I32x4,
+}
+
+impl Quad {
+    fn new(walkers: &[DdaWalker], max_lengths: &[f32], active: [bool; 4]) -> Self {
+        let f32s = |value: fn(&DdaWalker) -> f32| F32x4::new(array::from_fn(|i| value(&walkers[i])));
+        let i32s = |value: fn(&DdaWalker) -> i32| I32x4::new(array::from_fn(|i| value(&walkers[i])));
+
+        Self {
+            cell: (i32s(|w| w.cell.0), i32s(|w| w.cell.1)),
+            step: (i32s(|w| w.step.0), i32s(|w| w.step.1)),
+            ray_unit_step_size: (f32s(|w| w.ray_unit_step_size.0), f32s(|w| w.ray_unit_step_size.1)),
+            first_length: (f32s(|w| w.first_length.0), f32s(|w| w.first_length.1)),
+            crossed: (i32s(|w| w.crossed.0 as i32), i32s(|w| w.crossed.1 as i32)),
+            ray_length_1d: (f32s(|w| w.ray_length_1d.0), f32s(|w| w.ray_length_1d.1)),
+            max_length: F32x4::new(array::from_fn(|i| max_lengths[i])),
+            active: I32x4::new(active.map(|active| -(active as i32))),
+            entered_length: F32x4::new([0.; 4]),
+            entered_vertical: I32x4::splat(0),
+        }
+    }
+
+    /// steps every active ray into its next cell the same way `DdaWalker::step` does, as long as
+    /// `open` says it can go through that cell. rays that cant or that have gone past their max
+    /// length stop where they are. returns how many rays are still active
+    fn step(&mut self, open: impl Fn((i32, i32)) -> bool) -> u32 {
+        let vertical = self.ray_length_1d.0.lt(self.ray_length_1d.1);
+        let distance = F32x4::select(vertical, self.ray_length_1d.0, self.ray_length_1d.1);
+        let cell_x = self.cell.0.add(vertical.and(self.step.0));
+        let cell_y = self.cell.1.add(vertical.and_not(self.step.1));
+
+        // every active ray is stepped before looking at the map, rays almost always go into an open
+        // cell so the cpu can carry on stepping while the map is being looked at
+        let (crossed, ray_length_1d, cell) = (self.crossed, self.ray_length_1d, self.cell);
+        let (x, y) = (self.active.and(vertical), vertical.and_not(self.active));
+        self.crossed = (self.crossed.0.sub(x), self.crossed.1.sub(y));
+        self.ray_length_1d = (
+            F32x4::select(x, self.first_length.0.add(self.crossed.0.to_f32().mul(self.ray_unit_step_size.0)), self.ray_length_1d.0),
+            F32x4::select(y, self.first_length.1.add(self.crossed.1.to_f32().mul(self.ray_unit_step_size.1)), self.ray_length_1d.1),
+        );
+        self.cell = (I32x4::select(self.active, cell_x, self.cell.0), I32x4::select(self.active, cell_y, self.cell.1));
+        self.entered_length = F32x4::select(self.active, distance, self.entered_length);
+        self.entered_vertical = I32x4::select(self.active, vertical, self.entered_vertical);
+
+        // neighbouring rays are usually going into the same cell so the map is only looked at once for them
+        let (xs, ys) = (cell_x.to_array(), cell_y.to_array());
+        let same = cell_x.eq(I32x4::splat(xs[0])).and(cell_y.eq(I32x4::splat(ys[0])));
+        let active = self.active.bits();
+        let blocked = if same.bits() == 0b1111 {
+            if open((xs[0], ys[0])) { 0 } else { active }
+        } else {
+            (0..4).filter(|i| active >> i & 1 == 1 && !open((xs[*i], ys[*i]))).map(|i| 1 << i).sum()
+        };
+
+        // rays going into a cell they have to look at more closely are put back where they were
+        if blocked != 0 {
+            let blocked = I32x4::new(array::from_fn(|i| -((blocked >> i & 1) as i32)));
+            self.crossed = (I32x4::select(blocked, crossed.0, self.crossed.0), I32x4::select(blocked, crossed.1, self.crossed.1));
+            self.ray_length_1d = (
+                F32x4::select(blocked, ray_length_1d.0, self.ray_length_1d.0),
+                F32x4::select(blocked, ray_length_1d.1, self.ray_length_1d.1),
+            );
+            self.cell = (I32x4::select(blocked, cell.0, self.cell.0), I32x4::select(blocked, cell.1, self.cell.1));
+            self.active = blocked.and_not(self.active);
+        }
+
+        self.active = self.active.and(distance.lt(self.max_length));
+        self.active.bits().count_ones()
+    }
+
+    /// puts where the rays got to back into their walkers, returns the length of each ray
+    /// and if the edge was vertical when it went into the cell it stopped in
+    fn store(&self, walkers: &mut [DdaWalker]) -> [(f32, bool); 4] {
+        let cell = (self.cell.0.to_array(), self.cell.1.to_array());
+        let crossed = (self.crossed.0.to_array(), self.crossed.1.to_array());
+        let ray_length_1d = (self.ray_length_1d.0.to_array(), self.ray_length_1d.1.to_array());
+        for (i, walker) in walkers.iter_mut().enumerate() {
+            walker.cell = (cell.0[i], cell.1[i]);
+            walker.crossed = (crossed.0[i] as u32, crossed.1[i] as u32);
+            walker.ray_length_1d = (ray_length_1d.0[i], ray_length_1d.1[i]);
+        }
+
+        let (length, vertical) = (self.entered_length.to_array(), self.entered_vertical.bits());
+        array::from_fn(|i| (length[i], vertical >> i & 1 == 1))
+    }
+}
+
+impl<T: Map + std::marker::Sync> RayCastEngine<T> {
+    /// casts 4 rays from the same position at once and gives exactly the same results as `cast_ray`
+    /// would for each of them. the rays are stepped together with simd while they are going through
+    /// empty cells and each is finished on its own from the first cell that isnt empty, so this is
+    /// only faster for rays that go through the same cells, like neighbouring columns on the screen
+    pub fn cast_ray_packet4(&self, pos: (f32, f32), angles: [f32; 4], max_distance: f32) -> [RayData; 4] {
+        let [rays] = self.cast_quads(pos, [angles], max_distance);
+        rays
+    }
+
+    /// same as `cast_ray_packet4` but for 8 rays
+    pub fn cast_ray_packet8(&self, pos: (f32, f32), angles: [f32; 8], max_distance: f32) -> [RayData; 8] {
+        let [a, b] = self.cast_quads(pos, [array::from_fn(|i| angles[i]), array::from_fn(|i| angles[i + 4])], max_distance);
+        array::from_fn(|i| if i < 4 { a[i] } else { b[i - 4] })
+    }
+
+    /// casts any number of rays up to a full packet, rays left over at the end of the angles are casted one by one
+    pub(crate) fn cast_packet(&self, pos: (f32, f32), angles: &[f32], max_distance: f32) -> Vec<RayData> {
+        match <[f32; PACKET_SIZE]>::try_from(angles) {
+            Ok(angles) => self.cast_ray_packet8(pos, angles, max_distance).to_vec(),
+            Err(_) => angles.iter().map(|angle| self.cast_ray(pos, *angle, max_distance)).collect(),
+        }
+    }
+
+    /// steps groups of 4 rays together until most of them have stopped, then finishes each ray on its own
+    fn cast_quads<const Q: usize>(&self, pos: (f32, f32), angles: [[f32; 4]; Q], max_distance: f32) -> [[RayData; 4]; Q] {
+        let grids = angles.map(|angles| angles.map(|angle| self.grid_ray(pos, angle)));
+        let mut walkers: [[DdaWalker; 4]; Q] = array::from_fn(|q| array::from_fn(|i| DdaWalker::new(grids[q][i].pos, grids[q][i].dir)));
+
+        // rays starting in a cell that isnt empty could hit a thin wall in it so they dont step at all
+        let mut quads: [Quad; Q] = array::from_fn(|q| {
+            let max_lengths = grids[q].each_ref().map(|grid| grid.length(max_distance));
+            Quad::new(&walkers[q], &max_lengths, walkers[q].map(|walker| self.open(walker.cell)))
+        });
+
+        // once most of the rays have stopped the rest are quicker to finish on their own
+        let mut active = 4 * Q as u32;
+        while active * 2 > 4 * Q as u32 {
+            active = quads.iter_mut().map(|quad| quad.step(|cell| self.open(cell))).sum();
+        }
+
+        array::from_fn(|q| {
+            let entered = quads[q].store(&mut walkers[q]);
+            array::from_fn(|i| self.walk_ray(&grids[q][i], walkers[q][i], entered[i], max_distance, None))
+        })
+    }
+
+    /// checks if a ray can go through a cell without stopping, which is
+    /// when it is an empty cell in the map without a portal in it
+    fn open(&self, cell: (i32, i32)) -> bool {
+        self.cell_at(cell) == Some(0) && self.portal_at(cell).is_none()
+    }
+}
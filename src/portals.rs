@@ -0,0 +1,63 @@
+use crate::Float;
+
+/// where a ray entering a portal cell comes out, the ray carries on into
+/// the target cell as if it had come from the cell before the portal
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Portal {
+    pub target: (usize, usize),
+
+    /// how many quarter turns from the x axis towards the y axis the ray is turned by
+    pub rotation: u8,
+}
+
+impl Portal {
+    /// the transform that moves points in the portal cell at `cell` to the same spot in the target cell
+    pub fn transform<F: Float>(&self, cell: (usize, usize)) -> PortalTransform<F> {
+        let rotation = self.rotation % 4;
+        let half = F::from_f32(0.5);
+        let center = PortalTransform { rotation, offset: (F::ZERO, F::ZERO) }.apply_dir((F::from_i32(cell.0 as i32) + half, F::from_i32(cell.1 as i32) + half));
+        PortalTransform {
+            rotation,
+            offset: (F::from_i32(self.target.0 as i32) + half - center.0, F::from_i32(self.target.1 as i32) + half - center.1),
+        }
+    }
+}
+
+/// a rotation by quarter turns followed by a move, used to take points
+/// and directions from one side of a portal to the other
+#[derive(Default, Clone, Copy, PartialEq, Debug)]
+pub struct PortalTransform<F = f32> {
+    /// how many quarter turns from the x axis towards the y axis
+    pub rotation: u8,
+    pub offset: (F, F),
+}
+
+impl<F: Float> PortalTransform<F> {
+    /// rotates a direction, directions arent moved by the offset
+    pub fn apply_dir(&self, dir: (F, F)) -> (F, F) {
+        match self.rotation % 4 {
+            0 => dir,
+            1 => (-dir.1, dir.0),
+            2 => (-dir.0, -dir.1),
+            _ => (dir.1, -dir.0),
+        }
+    }
+
+    pub fn apply_point(&self, point: (F, F)) -> (F, F) {
+        let rotated = self.apply_dir(point);
+        (rotated.0 + self.offset.0, rotated.1 + self.offset.1)
+    }
+
+    /// how much an angle is turned by, in radians
+    pub fn angle(&self) -> F {
+        F::from_u32((self.rotation % 4) as u32) * F::FRAC_PI_2
+    }
+
+    /// the transform that does this one followed by `other`
+    pub fn then(&self, other: &PortalTransform<F>) -> PortalTransform<F> {
+        PortalTransform {
+            rotation: (self.rotation + other.rotation) % 4,
+            offset: other.apply_point(self.offset),
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use std::collections::BTreeSet;
+
+use crate::Map;
+
+/// the euclidean distance from anywhere on a map to the nearest wall, negative inside of walls where
+/// it is the distance to the nearest empty cell. distances are in cells and walls fill their whole
+/// cell, with everything outside of the map being wall. useful for sphere tracing, soft shadows and
+/// how much room there is to move around. unlike `DistanceField` this is worked out exactly.
+/// the distances are kept at the corners of the cells, the nearest point of a wall to a corner is
+/// always the corner of a wall cell so each corner only has to look at other corners
+pub struct SignedDistanceField {
+    size: (usize, usize),
+
+    /// which cells are walls, kept so changed cells can be found
+    solid: Vec<bool>,
+
+    /// the squared distance from each corner to the nearest wall corner and to the nearest
+    /// empty corner in the same column of corners, what the rows are worked out from
+    column_solid: Vec<f64>,
+    column_empty: Vec<f64>,
+
+    /// the signed distance at each corner
+    distances: Vec<f32>,
+}
+
+impl SignedDistanceField {
+    /// works out the field for a map, cells with a value above 0 are walls
+    pub fn new<M: Map>(map: &M, map_size: (usize, usize)) -> Self {
+        let corners = (map_size.0 + 1) * (map_size.1 + 1);
+        let mut field = Self {
+            size: map_size,
+            solid: (0..map_size.0 * map_size.1)
+                .map(|i| map.get_cell(i % map_size.0, i / map_size.0).is_some_and(|val| val > 0))
+                .collect(),
+            column_solid: vec![0.; corners],
+            column_empty: vec![0.; corners],
+            distances: vec![0.; corners],
+        };
+
+        for x in 0..=map_size.0 {
+            field.update_column(x);
+        }
+        for y in 0..=map_size.1 {
+            field.update_row(y);
+        }
+        field
+    }
+
+    pub fn size(&self) -> (usize, usize) {
+        self.size
+    }
+
+    /// updates the field after the given cells of the map have changed, only the
+    /// parts of the field that could be different are worked out again
+    pub fn update<M: Map>(&mut self, map: &M, cells: impl IntoIterator<Item = (usize, usize)>) {
+        let mut columns = BTreeSet::new();
+        for (x, y) in cells {
+            if x >= self.size.0 || y >= self.size.1 {
+                continue;
+            }
+            let solid = map.get_cell(x, y).is_some_and(|val| val > 0);
+            if self.solid[y * self.size.0 + x] != solid {
+                self.solid[y * self.size.0 + x] = solid;
+                columns.extend([x, x + 1]);
+            }
+        }
+
+        // only the rows where a column changed have to be done again
+        let mut rows = BTreeSet::new();
+        for x in columns {
+            rows.extend(self.update_column(x));
+        }
+        for y in rows {
+            self.update_row(y);
+        }
+    }
+
+    /// the signed distance at a corner of the cells, the corner at (x, y) is the top left corner of cell (x, y)
+    pub fn corner(&self, x: usize, y: usize) -> Option<f32> {
+        if x > self.size.0 || y > self.size.1 {
+            return None;
+        }
+        Some(self.distances[self.corner_index(x, y)])
+    }
+
+    /// the signed distance at a point in grid space, where each cell is 1 unit wide,
+    /// worked out by blending between the corners of the cell it is in.
+    /// points outside of the map use the nearest point on its edge. the corners are exact
+    /// and the blend is never more than about 0.7 cells off from the real distance
+    pub fn distance(&self, point: (f32, f32)) -> f32 {
+        let (corners, t) = self.corners_around(point);
+        let top = corners[0] + (corners[1] - corners[0]) * t.0;
+        let bottom = corners[2] + (corners[3] - corners[2]) * t.0;
+        top + (bottom - top) * t.1
+    }
+
+    /// the direction the distance goes up fastest in at a point in grid space, pointing away from the nearest
+    /// wall. this is the slope of the blend used by `distance` so it isnt always exactly 1 long
+    pub fn gradient(&self, point: (f32, f32)) -> (f32, f32) {
+        let (corners, t) = self.corners_around(point);
+        (
+            (corners[1] - corners[0]) * (1. - t.1) + (corners[3] - corners[2]) * t.1,
+            (corners[2] - corners[0]) * (1. - t.0) + (corners[3] - corners[1]) * t.0,
+        )
+    }
+
+    /// the distances at the top left, top right, bottom left and bottom right corners
+    /// of the cell a point is in, along with how far across the cell the point is
+    fn corners_around(&self, point: (f32, f32)) -> ([f32; 4], (f32, f32)) {
+        let x = point.0.clamp(0., self.size.0 as f32);
+        let y = point.1.clamp(0., self.size.1 as f32);
+
+        // points on the right or bottom edge of the map use the last cell
+        let cell = ((x as usize).min(self.size.0.saturating_sub(1)), (y as usize).min(self.size.1.saturating_sub(1)));
+        let corner = |dx: usize, dy: usize| self.corner(cell.0 + dx, cell.1 + dy).unwrap_or(0.);
+
+        (
+            [corner(0, 0), corner(1, 0), corner(0, 1), corner(1, 1)],
+            (x - cell.0 as f32, y - cell.1 as f32),
+        )
+    }
+
+    fn corner_index(&self, x: usize, y: usize) -> usize {
+        y * (self.size.0 + 1) + x
+    }
+
+    /// checks if a cell is a wall, everything outside of the map is
+    fn is_solid(&self, x: isize, y: isize) -> bool {
+        if x < 0 || y < 0 || x as usize >= self.size.0 || y as usize >= self.size.1 {
+            return true;
+        }
+        self.solid[y as usize * self.size.0 + x as usize]
+    }
+
+    /// works out the distances along a column of corners again, returns the rows that changed
+    fn update_column(&mut self, x: usize) -> Vec<usize> {
+        let height = self.size.1 + 1;
+
+        // a corner touching a wall cell is on the wall and one touching an empty cell is on empty space
+        let touching = |y: usize| {
+            let cells = [(-1, -1), (0, -1), (-1, 0), (0, 0)].map(|(dx, dy)| self.is_solid(x as isize + dx, y as isize + dy));
+            (cells.contains(&true), cells.contains(&false))
+        };
+        let (on_solid, on_empty): (Vec<bool>, Vec<bool>) = (0..height).map(touching).unzip();
+
+        let solid = squared_distances(&on_solid.iter().map(|on| if *on { 0. } else { f64::INFINITY }).collect::<Vec<_>>());
+        let empty = squared_distances(&on_empty.iter().map(|on| if *on { 0. } else { f64::INFINITY }).collect::<Vec<_>>());
+
+        let mut changed = Vec::new();
+        for y in 0..height {
+            let i = self.corner_index(x, y);
+            if self.column_solid[i] != solid[y] || self.column_empty[i] != empty[y] {
+                self.column_solid[i] = solid[y];
+                self.column_empty[i] = empty[y];
+                changed.push(y);
+            }
+        }
+        changed
+    }
+
+    /// works out the distances along a row of corners again from the columns
+    fn update_row(&mut self, y: usize) {
+        let row = self.corner_index(0, y)..self.corner_index(0, y + 1);
+        let solid = squared_distances(&self.column_solid[row.clone()]);
+        let empty = squared_distances(&self.column_empty[row.clone()]);
+
+        // one of the distances is always 0, and both are for corners right on the edge of a wall
+        for (distance, (solid, empty)) in self.distances[row].iter_mut().zip(solid.iter().zip(&empty)) {
+            *distance = (solid.sqrt() - empty.sqrt()) as f32;
+        }
+    }
+}
+
+/// for each point along a line, the smallest squared distance to another point plus the value of that point.
+/// this is the lower envelope of the parabolas from each point (felzenszwalb and huttenlocher), infinite
+/// values are points that arent there
+fn squared_distances(values: &[f64]) -> Vec<f64> {
+    // the points whose parabolas make up the envelope along with where each one starts being the lowest
+    let mut envelope: Vec<(usize, f64)> = Vec::new();
+    let parabola = |q: usize| values[q] + (q * q) as f64;
+
+    for (q, value) in values.iter().enumerate() {
+        if value.is_infinite() {
+            continue;
+        }
+
+        let mut start = f64::NEG_INFINITY;
+        while let Some(&(p, p_start)) = envelope.last() {
+            // where the parabolas of p and q cross
+            let crossing = (parabola(q) - parabola(p)) / (2 * (q - p)) as f64;
+            if crossing <= p_start {
+                envelope.pop();
+            } else {
+                start = crossing;
+                break;
+            }
+        }
+        envelope.push((q, start));
+    }
+
+    let mut k = 0;
+    (0..values.len())
+        .map(|x| {
+            if envelope.is_empty() {
+                return f64::INFINITY;
+            }
+            while k + 1 < envelope.len() && envelope[k + 1].1 < x as f64 {
+                k += 1;
+            }
+            let q = envelope[k].0;
+            (x as f64 - q as f64) * (x as f64 - q as f64) + values[q]
+        })
+        .collect()
+}
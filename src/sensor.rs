@@ -0,0 +1,180 @@
+use crate::{Map, RayCastEngine};
+
+/// the noise added to the ranges a sensor measures
+#[derive(Default, Clone, Copy, PartialEq, Debug)]
+pub struct SensorNoise {
+    /// the standard deviation of the gaussian noise added to every range
+    pub range_stddev: f32,
+
+    /// extra standard deviation for every unit of range, since far away hits are usually noisier
+    pub range_stddev_per_unit: f32,
+
+    /// the chance from 0 to 1 of a ray not coming back at all
+    pub dropout: f32,
+}
+
+/// a simulated lidar or other range sensor, it casts rays evenly spaced over an arc
+/// and measures how far away the walls they hit are
+pub struct RangeSensor {
+    /// how many rays are casted for each scan
+    pub rays: usize,
+
+    /// the angle the rays are spread over, centered on the way the sensor is facing.
+    /// an arc of a whole turn spaces the rays all the way around without doubling up
+    pub arc: f32,
+
+    /// hits closer than `min_range` or farther than `max_range` arent measured
+    pub min_range: f32,
+    pub max_range: f32,
+
+    pub noise: SensorNoise,
+
+    rng: SensorRng,
+}
+
+/// what one ray of a scan measured
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Reading {
+    /// the ray hit something this far away
+    Hit(f32),
+
+    /// nothing was hit before the max range, so everything along the ray up to it is empty
+    MaxRange,
+
+    /// nothing came back because the ray was dropped or what it hit was closer than the min range,
+    /// so nothing is known about what is along the ray
+    Dropout,
+}
+
+impl Reading {
+    /// the range of a hit, or None if the ray didnt hit anything
+    pub fn range(self) -> Option<f32> {
+        match self {
+            Reading::Hit(range) => Some(range),
+            _ => None,
+        }
+    }
+}
+
+/// the ranges measured by one scan of a sensor
+#[derive(Clone, Debug)]
+pub struct Scan {
+    /// where the sensor was and the angle it was facing
+    pub pos: (f32, f32),
+    pub heading: f32,
+
+    /// the max range of the sensor, which is how far rays that read `MaxRange` went
+    pub max_range: f32,
+
+    /// the angle of each ray from the way the sensor was facing
+    pub angles: Vec<f32>,
+
+    /// what each ray measured
+    pub ranges: Vec<Reading>,
+}
+
+impl RangeSensor {
+    /// creates a sensor without any noise, set `noise` and `seed` to add some
+    pub fn new(rays: usize, arc: f32, min_range: f32, max_range: f32) -> Self {
+        Self {
+            rays,
+            arc,
+            min_range,
+            max_range,
+            noise: SensorNoise::default(),
+            rng: SensorRng(0),
+        }
+    }
+
+    /// restarts the random numbers used for the noise, sensors with the same seed give the same scans
+    pub fn seed(&mut self, seed: u64) {
+        self.rng = SensorRng(seed);
+    }
+
+    /// the angle of each ray from the way the sensor is facing
+    pub fn angles(&self) -> Vec<f32> {
+        let gaps = if self.arc >= std::f32::consts::TAU {
+            self.rays
+        } else {
+            self.rays.saturating_sub(1).max(1)
+        };
+        let start = if self.rays > 1 { -self.arc / 2. } else { 0. };
+        (0..self.rays).map(|i| start + self.arc * i as f32 / gaps as f32).collect()
+    }
+
+    /// measures the map from `pos` while facing `heading`, every ray is casted with `cast_rays_multi`
+    pub fn scan<T: Map + std::marker::Sync>(&mut self, engine: &RayCastEngine<T>, pos: (f32, f32), heading: f32) -> Scan {
+        let angles = self.angles();
+        let mut rays = engine.cast_rays_multi(pos, angles.iter().map(|angle| heading + angle).collect(), self.max_range);
+
+        // the noise is added in the same order every time so the same seed gives the same scan
+        rays.sort_by_key(|(i, _)| *i);
+        let ranges = rays
+            .into_iter()
+            .map(|(_, ray)| {
+                let dropped = self.rng.next_f32() < self.noise.dropout;
+                let stddev = self.noise.range_stddev + self.noise.range_stddev_per_unit * ray.ray_length;
+                let range = ray.ray_length + self.rng.next_gaussian() * stddev;
+
+                if dropped || (ray.hit_val.is_some() && range < self.min_range) {
+                    Reading::Dropout
+                } else if ray.hit_val.is_none() || range > self.max_range {
+                    Reading::MaxRange
+                } else {
+                    Reading::Hit(range)
+                }
+            })
+            .collect();
+
+        Scan { pos, heading, max_range: self.max_range, angles, ranges }
+    }
+}
+
+impl Scan {
+    /// the point each ray that came back hit, relative to the sensor with x going the way it was facing
+    pub fn local_points(&self) -> Vec<(f32, f32)> {
+        self.angles
+            .iter()
+            .zip(&self.ranges)
+            .filter_map(|(angle, reading)| reading.range().map(|range| (angle.cos() * range, angle.sin() * range)))
+            .collect()
+    }
+
+    /// the point each ray that came back hit, in world space. rays are treated as
+    /// straight lines like a real sensor would, even if they went through portals or mirrors
+    pub fn points(&self) -> Vec<(f32, f32)> {
+        self.angles
+            .iter()
+            .zip(&self.ranges)
+            .filter_map(|(angle, reading)| {
+                let angle = self.heading + angle;
+                reading.range().map(|range| (self.pos.0 + angle.cos() * range, self.pos.1 + angle.sin() * range))
+            })
+            .collect()
+    }
+}
+
+/// a small splitmix64 generator so scans dont need a random number crate and come out the same everywhere
+struct SensorRng(u64);
+
+impl SensorRng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// a number from 0 up to but not including 1
+    fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// a number from the normal distribution with a mean of 0 and a standard deviation of 1
+    fn next_gaussian(&mut self) -> f32 {
+        // box muller, 1 - u keeps the log away from 0
+        let (u, v) = (1. - self.next_f32(), self.next_f32());
+        (-2. * u.ln()).sqrt() * (std::f32::consts::TAU * v).cos()
+    }
+}
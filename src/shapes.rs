@@ -0,0 +1,184 @@
+use crate::Float;
+
+/// the shape of the wall in a cell
+#[derive(Default, Clone, Copy, PartialEq, Debug)]
+pub enum CellShape {
+    /// the wall fills the whole cell
+    #[default]
+    Block,
+
+    /// a thin wall crossing the cell, it runs along the y axis `offset` of the way across the
+    /// cell when `vertical` and along the x axis otherwise. an offset of 0.5 puts the wall in
+    /// the middle of the cell and offsets closer to 0 or 1 make recessed alcoves
+    Thin { vertical: bool, offset: f32 },
+
+    /// a thin wall like `Thin` that slides sideways out of the cell, `open` goes
+    /// from 0 when the door is closed to 1 when it is all the way open
+    Door { vertical: bool, offset: f32, open: f32 },
+
+    /// a straight wall between two points inside the cell, the points go from (0, 0)
+    /// at the top left of the cell to (1, 1) at the bottom right, corner to corner
+    /// segments make diagonal walls
+    Segment { start: (f32, f32), end: (f32, f32) },
+
+    /// a round pillar inside the cell, the center is placed the same way as the points
+    /// of a segment and the pillar should fit inside the cell
+    Circle { center: (f32, f32), radius: f32 },
+}
+
+/// where a ray hit the wall in a cell
+#[derive(Clone, Copy)]
+pub(crate) struct ShapeHit<F = f32> {
+    pub distance: F,
+    pub texture_u: F,
+    pub collided_vertical: bool,
+    pub normal: (F, F),
+}
+
+impl CellShape {
+    /// intersects a ray with the wall in `cell`, `span` holds the lengths of the ray when it enters
+    /// and leaves the cell and `collided_vertical` is if it entered through a vertical edge
+    pub(crate) fn intersect<F: Float>(&self, cell: (i32, i32), pos: (F, F), dir: (F, F), span: (F, F), collided_vertical: bool) -> Option<ShapeHit<F>> {
+        match *self {
+            CellShape::Block => Some(ShapeHit {
+                distance: span.0,
+                texture_u: edge_u(pos, dir, span.0, collided_vertical),
+                collided_vertical,
+                normal: edge_normal(dir, collided_vertical),
+            }),
+            CellShape::Thin { vertical, offset } => plane_hit(cell, pos, dir, span, vertical, offset, 0.),
+            CellShape::Door { vertical, offset, open } => plane_hit(cell, pos, dir, span, vertical, offset, open),
+            CellShape::Segment { start, end } => segment_hit(cell, pos, dir, span, start, end),
+            CellShape::Circle { center, radius } => circle_hit(cell, pos, dir, span, center, radius),
+        }
+    }
+}
+
+/// how far along a cell edge a ray crosses it at the given length
+pub(crate) fn edge_u<F: Float>(pos: (F, F), dir: (F, F), distance: F, collided_vertical: bool) -> F {
+    let coord = if collided_vertical {
+        pos.1 + dir.1 * distance
+    } else {
+        pos.0 + dir.0 * distance
+    };
+    coord - coord.floor()
+}
+
+/// the normal of a cell edge crossed by a ray, pointing back towards where the ray came from
+pub(crate) fn edge_normal<F: Float>(dir: (F, F), collided_vertical: bool) -> (F, F) {
+    if collided_vertical {
+        (-dir.0.signum(), F::ZERO)
+    } else {
+        (F::ZERO, -dir.1.signum())
+    }
+}
+
+/// intersects a ray with a plane crossing the cell between the lengths in `span`,
+/// the part of the plane before `open` along it has slid out of the cell and cant be hit
+fn plane_hit<F: Float>(cell: (i32, i32), pos: (F, F), dir: (F, F), span: (F, F), vertical: bool, offset: f32, open: f32) -> Option<ShapeHit<F>> {
+    let (offset, open) = (F::from_f32(offset), F::from_f32(open));
+
+    // splits everything into the axis the plane crosses and the axis it runs along
+    let (p, d, c, q, e, c_along) = if vertical {
+        (pos.0, dir.0, cell.0, pos.1, dir.1, cell.1)
+    } else {
+        (pos.1, dir.1, cell.1, pos.0, dir.0, cell.0)
+    };
+
+    // rays running alongside the plane never hit it
+    if d == F::ZERO {
+        return None;
+    }
+
+    let distance = (F::from_i32(c) + offset - p) / d;
+    if distance < span.0 || distance > span.1 {
+        return None;
+    }
+
+    let u = q + e * distance - F::from_i32(c_along);
+    if u < open {
+        return None;
+    }
+
+    Some(ShapeHit {
+        distance,
+        texture_u: u - open,
+        collided_vertical: vertical,
+        normal: edge_normal(dir, vertical),
+    })
+}
+
+/// intersects a ray with a segment inside the cell between the lengths in `span`,
+/// the texture runs from the start of the segment to the end
+fn segment_hit<F: Float>(cell: (i32, i32), pos: (F, F), dir: (F, F), span: (F, F), start: (f32, f32), end: (f32, f32)) -> Option<ShapeHit<F>> {
+    let (start, end) = ((F::from_f32(start.0), F::from_f32(start.1)), (F::from_f32(end.0), F::from_f32(end.1)));
+    let a = (F::from_i32(cell.0) + start.0, F::from_i32(cell.1) + start.1);
+    let along = (end.0 - start.0, end.1 - start.1);
+    let to_start = (a.0 - pos.0, a.1 - pos.1);
+
+    // rays parallel to the segment never hit it
+    let denom = cross(dir, along);
+    if denom == F::ZERO {
+        return None;
+    }
+
+    let distance = cross(to_start, along) / denom;
+    let u = cross(to_start, dir) / denom;
+    if distance < span.0 || distance > span.1 || !(u >= F::ZERO && u <= F::ONE) {
+        return None;
+    }
+
+    // the normal faces whichever side of the segment the ray came from
+    let length = (along.0 * along.0 + along.1 * along.1).sqrt();
+    let mut normal = (-along.1 / length, along.0 / length);
+    if normal.0 * dir.0 + normal.1 * dir.1 > F::ZERO {
+        normal = (-normal.0, -normal.1);
+    }
+
+    Some(ShapeHit {
+        distance,
+        texture_u: u,
+        collided_vertical: normal.0.abs() > normal.1.abs(),
+        normal,
+    })
+}
+
+/// intersects a ray with a circle inside the cell between the lengths in `span`,
+/// the texture wraps around the circle once
+fn circle_hit<F: Float>(cell: (i32, i32), pos: (F, F), dir: (F, F), span: (F, F), center: (f32, f32), radius: f32) -> Option<ShapeHit<F>> {
+    let radius = F::from_f32(radius);
+    let c = (F::from_i32(cell.0) + F::from_f32(center.0), F::from_i32(cell.1) + F::from_f32(center.1));
+    let from_center = (pos.0 - c.0, pos.1 - c.1);
+
+    // solves for where the ray is `radius` away from the center, the direction is normalized.
+    // this goes through the point on the ray closest to the center so it stays precise when
+    // the start of the ray is far away, like after bouncing off of mirrors
+    let closest = -(from_center.0 * dir.0 + from_center.1 * dir.1);
+    let offset = (from_center.0 + dir.0 * closest, from_center.1 + dir.1 * closest);
+    let discriminant = radius * radius - (offset.0 * offset.0 + offset.1 * offset.1);
+    if discriminant < F::ZERO {
+        return None;
+    }
+
+    // only the near side of the circle can be seen from outside of it
+    let distance = closest - discriminant.sqrt();
+    if distance < span.0 || distance > span.1 {
+        return None;
+    }
+
+    let hit = (pos.0 + dir.0 * distance, pos.1 + dir.1 * distance);
+    let length = ((hit.0 - c.0) * (hit.0 - c.0) + (hit.1 - c.1) * (hit.1 - c.1)).sqrt();
+    let normal = ((hit.0 - c.0) / length, (hit.1 - c.1) / length);
+
+    Some(ShapeHit {
+        distance,
+        texture_u: (normal.1.atan2(normal.0) / F::TAU).rem_euclid(F::ONE),
+        collided_vertical: normal.0.abs() > normal.1.abs(),
+        normal,
+    })
+}
+
+/// the z component of the cross product of two 2D vectors
+fn cross<F: Float>(a: (F, F), b: (F, F)) -> F {
+    a.0 * b.1 - a.1 * b.0
+}
@@ -0,0 +1,268 @@
+// four lane vectors for stepping packets of rays together. x86_64 always has sse2 so the vectors
+// use it there, anywhere else they fall back to plain arrays that work out the same values
+
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+mod lanes {
+    use std::arch::x86_64::*;
+
+    /// calls sse and sse2 intrinsics, every unsafe block in this module goes through here
+    macro_rules! sse {
+        ($($body:tt)*) => {
+            // SAFETY: the intrinsics are only unsafe because the cpu might not have the instructions they use.
+            // this module is only built when sse2 is enabled, which it always is on x86_64 since every x86_64
+            // cpu has sse and sse2. none of the intrinsics used here take pointers or touch memory, they only
+            // work on the values in the vectors so there is nothing else that could go wrong
+            unsafe { $($body)* }
+        };
+    }
+
+    /// four floats
+    #[derive(Clone, Copy)]
+    pub struct F32x4(__m128);
+
+    /// four ints, also used as masks where every bit of a lane is set when it is true
+    #[derive(Clone, Copy)]
+    pub struct I32x4(__m128i);
+
+    impl F32x4 {
+        #[inline]
+        pub fn new(lanes: [f32; 4]) -> Self {
+            sse! {
+                Self(_mm_setr_ps(lanes[0], lanes[1], lanes[2], lanes[3]))
+            }
+        }
+
+        #[inline]
+        pub fn to_array(self) -> [f32; 4] {
+            sse! {
+                let lane = |i| match i {
+                    0 => _mm_cvtss_f32(self.0),
+                    1 => _mm_cvtss_f32(_mm_shuffle_ps::<0b01>(self.0, self.0)),
+                    2 => _mm_cvtss_f32(_mm_shuffle_ps::<0b10>(self.0, self.0)),
+                    _ => _mm_cvtss_f32(_mm_shuffle_ps::<0b11>(self.0, self.0)),
+                };
+                [lane(0), lane(1), lane(2), lane(3)]
+            }
+        }
+
+        #[inline]
+        pub fn add(self, other: Self) -> Self {
+            sse! {
+                Self(_mm_add_ps(self.0, other.0))
+            }
+        }
+
+        #[inline]
+        pub fn mul(self, other: Self) -> Self {
+            sse! {
+                Self(_mm_mul_ps(self.0, other.0))
+            }
+        }
+
+        /// the lanes where `self < other`, lanes with NaN in them are false like they are for `<`
+        #[inline]
+        pub fn lt(self, other: Self) -> I32x4 {
+            sse! {
+                I32x4(_mm_castps_si128(_mm_cmplt_ps(self.0, other.0)))
+            }
+        }
+
+        /// takes the lanes from `a` where the mask is true and from `b` everywhere else
+        #[inline]
+        pub fn select(mask: I32x4, a: Self, b: Self) -> Self {
+            sse! {
+                let mask = _mm_castsi128_ps(mask.0);
+                Self(_mm_or_ps(_mm_and_ps(mask, a.0), _mm_andnot_ps(mask, b.0)))
+            }
+        }
+    }
+
+    impl I32x4 {
+        #[inline]
+        pub fn new(lanes: [i32; 4]) -> Self {
+            sse! {
+                Self(_mm_setr_epi32(lanes[0], lanes[1], lanes[2], lanes[3]))
+            }
+        }
+
+        #[inline]
+        pub fn splat(value: i32) -> Self {
+            sse! {
+                Self(_mm_set1_epi32(value))
+            }
+        }
+
+        #[inline]
+        pub fn to_array(self) -> [i32; 4] {
+            sse! {
+                [
+                    _mm_cvtsi128_si32(self.0),
+                    _mm_cvtsi128_si32(_mm_shuffle_epi32::<0b01>(self.0)),
+                    _mm_cvtsi128_si32(_mm_shuffle_epi32::<0b10>(self.0)),
+                    _mm_cvtsi128_si32(_mm_shuffle_epi32::<0b11>(self.0)),
+                ]
+            }
+        }
+
+        #[inline]
+        pub fn add(self, other: Self) -> Self {
+            sse! {
+                Self(_mm_add_epi32(self.0, other.0))
+            }
+        }
+
+        #[inline]
+        pub fn sub(self, other: Self) -> Self {
+            sse! {
+                Self(_mm_sub_epi32(self.0, other.0))
+            }
+        }
+
+        #[inline]
+        pub fn and(self, other: Self) -> Self {
+            sse! {
+                Self(_mm_and_si128(self.0, other.0))
+            }
+        }
+
+        /// the lanes of `other` where this mask is false
+        #[inline]
+        pub fn and_not(self, other: Self) -> Self {
+            sse! {
+                Self(_mm_andnot_si128(self.0, other.0))
+            }
+        }
+
+        #[inline]
+        pub fn eq(self, other: Self) -> Self {
+            sse! {
+                Self(_mm_cmpeq_epi32(self.0, other.0))
+            }
+        }
+
+        #[inline]
+        pub fn to_f32(self) -> F32x4 {
+            sse! {
+                F32x4(_mm_cvtepi32_ps(self.0))
+            }
+        }
+
+        /// one bit for each lane of a mask that is true, lane 0 in the lowest bit
+        #[inline]
+        pub fn bits(self) -> u32 {
+            sse! {
+                _mm_movemask_ps(_mm_castsi128_ps(self.0)) as u32
+            }
+        }
+
+        /// takes the lanes from `a` where the mask is true and from `b` everywhere else
+        #[inline]
+        pub fn select(mask: Self, a: Self, b: Self) -> Self {
+            sse! {
+                Self(_mm_or_si128(_mm_and_si128(mask.0, a.0), _mm_andnot_si128(mask.0, b.0)))
+            }
+        }
+    }
+}
+
+#[cfg(not(all(target_arch = "x86_64", target_feature = "sse2")))]
+mod lanes {
+    use std::array;
+
+    #[derive(Clone, Copy)]
+    pub struct F32x4([f32; 4]);
+
+    #[derive(Clone, Copy)]
+    pub struct I32x4([i32; 4]);
+
+    impl F32x4 {
+        #[inline]
+        pub fn new(lanes: [f32; 4]) -> Self {
+            Self(lanes)
+        }
+
+        #[inline]
+        pub fn to_array(self) -> [f32; 4] {
+            self.0
+        }
+
+        #[inline]
+        pub fn add(self, other: Self) -> Self {
+            Self(array::from_fn(|i| self.0[i] + other.0[i]))
+        }
+
+        #[inline]
+        pub fn mul(self, other: Self) -> Self {
+            Self(array::from_fn(|i| self.0[i] * other.0[i]))
+        }
+
+        #[inline]
+        pub fn lt(self, other: Self) -> I32x4 {
+            I32x4(array::from_fn(|i| -((self.0[i] < other.0[i]) as i32)))
+        }
+
+        #[inline]
+        pub fn select(mask: I32x4, a: Self, b: Self) -> Self {
+            Self(array::from_fn(|i| if mask.0[i] != 0 { a.0[i] } else { b.0[i] }))
+        }
+    }
+
+    impl I32x4 {
+        #[inline]
+        pub fn new(lanes: [i32; 4]) -> Self {
+            Self(lanes)
+        }
+
+        #[inline]
+        pub fn splat(value: i32) -> Self {
+            Self([value; 4])
+        }
+
+        #[inline]
+        pub fn to_array(self) -> [i32; 4] {
+            self.0
+        }
+
+        #[inline]
+        pub fn add(self, other: Self) -> Self {
+            Self(array::from_fn(|i| self.0[i].wrapping_add(other.0[i])))
+        }
+
+        #[inline]
+        pub fn sub(self, other: Self) -> Self {
+            Self(array::from_fn(|i| self.0[i].wrapping_sub(other.0[i])))
+        }
+
+        #[inline]
+        pub fn and(self, other: Self) -> Self {
+            Self(array::from_fn(|i| self.0[i] & other.0[i]))
+        }
+
+        #[inline]
+        pub fn and_not(self, other: Self) -> Self {
+            Self(array::from_fn(|i| !self.0[i] & other.0[i]))
+        }
+
+        #[inline]
+        pub fn eq(self, other: Self) -> Self {
+            Self(array::from_fn(|i| -((self.0[i] == other.0[i]) as i32)))
+        }
+
+        #[inline]
+        pub fn to_f32(self) -> F32x4 {
+            F32x4(self.0.map(|lane| lane as f32))
+        }
+
+        #[inline]
+        pub fn bits(self) -> u32 {
+            (0..4).map(|i| ((self.0[i] != 0) as u32) << i).sum()
+        }
+
+        #[inline]
+        pub fn select(mask: Self, a: Self, b: Self) -> Self {
+            Self(array::from_fn(|i| if mask.0[i] != 0 { a.0[i] } else { b.0[i] }))
+        }
+    }
+}
+
+pub(crate) use lanes::{F32x4, I32x4};
@@ -0,0 +1,456 @@
+use crate::grid::GridRay;
+use crate::shapes::{edge_normal, edge_u, ShapeHit};
+use crate::{cast_multi, CellShape, DdaWalker, Map, Portal, RayCastEngine, RayData};
+
+/// the part of a cell a span of a column comes from
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SpanKind {
+    /// the side of a wall
+    #[default]
+    Wall,
+
+    /// the face between a floor and a higher floor behind it
+    FloorStep,
+
+    /// the face between a ceiling and a lower ceiling behind it
+    CeilingStep,
+
+    /// the top of a floor, or of a wall shorter than the camera
+    Floor,
+
+    /// the underside of a ceiling
+    Ceiling,
+}
+
+impl<T: Map + std::marker::Sync> RayCastEngine<T> {
+    /// casts a ray that keeps going past anything that doesnt block the view from a
+    /// camera at `eye_height`, like walls shorter than the camera, and returns every
+    /// span that can be seen in the column ordered from nearest to farthest.
+    /// along with the walls this includes the steps between floors/ceilings of
+    /// different heights and the floors/ceilings themselves
+    pub fn cast_ray_spans(&self, pos: (f32, f32), angle: f32, max_distance: f32, eye_height: f32) -> Vec<RayData> {
+        cast_spans(&self.grid_ray(pos, angle), max_distance, eye_height, self.max_bounces, &SingleMap(self))
+    }
+
+    /// same as `cast_rays_multi` but uses `cast_ray_spans` for each ray
+    pub fn cast_rays_spans_multi(&self, pos: (f32, f32), angles: Vec<f32>, max_distance: f32, eye_height: f32) -> Vec<(usize, Vec<RayData>)> {
+        cast_multi(&angles, |angle| self.cast_ray_spans(pos, *angle, max_distance, eye_height))
+    }
+}
+
+/// a solid part of a column of the map, like a wall or the ground under a floor
+#[derive(Clone, Copy)]
+pub(crate) struct Solid {
+    pub bottom: f32,
+    pub top: f32,
+
+    /// the kind of span the sides of the solid are
+    pub kind: SpanKind,
+
+    /// the storey the sides and bottom of the solid belong to
+    pub layer: usize,
+
+    /// the storey the top of the solid belongs to, this is only different
+    /// for the slab between the ceiling of one storey and the floor of the next
+    pub top_layer: usize,
+
+    /// the map values of the cell in `layer` and `top_layer`
+    pub val: u32,
+    pub top_val: u32,
+
+    /// the shape of the wall, anything other than a block is a thin wall inside the cell
+    pub shape: CellShape,
+}
+
+/// fills `solids` with the solid parts of the given cell from the bottom up. `storeys` are
+/// stacked `storey_height` apart and heights are relative to the floor of the storey at `layer`
+pub(crate) fn build_column<M: Map>(storeys: &[M], storey_height: f32, layer: usize, cell: (usize, usize), solids: &mut Vec<Solid>) {
+    solids.clear();
+    let (x, y) = cell;
+
+    // the ceiling, storey and value of the cell in the storey below
+    let mut below: Option<(f32, usize, u32)> = None;
+
+    for (i, storey) in storeys.iter().enumerate() {
+        let base = (i as f32 - layer as f32) * storey_height;
+        let val = storey.get_cell(x, y).unwrap_or(0);
+        let floor = base + storey.get_floor_height(x, y);
+
+        match below {
+            // the ground under the lowest storey goes down forever
+            None => solids.push(Solid {
+                bottom: f32::NEG_INFINITY,
+                top: floor,
+                kind: SpanKind::FloorStep,
+                layer: i,
+                top_layer: i,
+                val,
+                top_val: val,
+                shape: CellShape::Block,
+            }),
+            // the slab between the ceiling of the storey below and the floor of this one
+            Some((ceil, below_layer, below_val)) if ceil.is_finite() => solids.push(Solid {
+                bottom: ceil,
+                top: floor.max(ceil),
+                kind: SpanKind::CeilingStep,
+                layer: below_layer,
+                top_layer: i,
+                val: below_val,
+                top_val: val,
+                shape: CellShape::Block,
+            }),
+            // the storey below is open to this one, so there is a hole in the floor
+            _ => {}
+        }
+
+        if val > 0 {
+            solids.push(Solid {
+                bottom: floor,
+                top: floor + storey.get_height(x, y),
+                kind: SpanKind::Wall,
+                layer: i,
+                top_layer: i,
+                val,
+                top_val: val,
+                shape: storey.get_shape(x, y),
+            });
+        }
+
+        below = Some((base + storey.get_ceil_height(x, y), i, val));
+    }
+
+    // the roof above the highest storey goes up forever
+    if let Some((ceil, below_layer, below_val)) = below {
+        if ceil.is_finite() {
+            solids.push(Solid {
+                bottom: ceil,
+                top: f32::INFINITY,
+                kind: SpanKind::CeilingStep,
+                layer: below_layer,
+                top_layer: below_layer,
+                val: below_val,
+                top_val: below_val,
+                shape: CellShape::Block,
+            });
+        }
+    }
+}
+
+/// the parts of a map the span caster needs to look at
+pub(crate) trait Columns {
+    /// fills `solids` with the solids of a cell, returns false if the cell is outside of the map
+    fn column(&self, cell: (i32, i32), solids: &mut Vec<Solid>) -> bool;
+
+    fn portal_at(&self, cell: (i32, i32)) -> Option<Portal>;
+
+    /// if the cell has a mirror the ray should bounce off of, only full block walls can be
+    /// mirrors here since the whole column is reflected
+    fn mirror_at(&self, cell: (i32, i32)) -> bool;
+}
+
+/// checks if a cell of a map has a full block wall that is a mirror
+pub(crate) fn block_mirror<M: Map>(map: &M, cell: (usize, usize)) -> bool {
+    map.is_mirror(cell.0, cell.1)
+        && map.get_cell(cell.0, cell.1).is_some_and(|val| val > 0)
+        && map.get_shape(cell.0, cell.1) == CellShape::Block
+}
+
+/// the columns of an engine with a single map
+struct SingleMap<'a, T>(&'a RayCastEngine<T>);
+
+impl<T: Map + std::marker::Sync> Columns for SingleMap<'_, T> {
+    fn column(&self, cell: (i32, i32), solids: &mut Vec<Solid>) -> bool {
+        if !self.0.in_bounds(cell) {
+            return false;
+        }
+        build_column(std::slice::from_ref(&self.0.map), 0., 0, (cell.0 as usize, cell.1 as usize), solids);
+        true
+    }
+
+    fn portal_at(&self, cell: (i32, i32)) -> Option<Portal> {
+        self.0.portal_at(cell)
+    }
+
+    fn mirror_at(&self, cell: (i32, i32)) -> bool {
+        self.0.in_bounds(cell) && block_mirror(&self.0.map, (cell.0 as usize, cell.1 as usize))
+    }
+}
+
+/// walks a ray through the map and collects every visible span. mirrors reflect the whole
+/// column as if they were full height, up to `max_bounces` times
+pub(crate) fn cast_spans(grid: &GridRay, max_distance: f32, eye_height: f32, max_bounces: u32, columns: &impl Columns) -> Vec<RayData> {
+    let (pos, ray_dir) = (grid.pos, grid.dir);
+    let max_distance = grid.length(max_distance);
+    let mut walker = DdaWalker::new(pos, ray_dir);
+
+    let mut view = View {
+        spans: Vec::new(),
+        eye_height,
+        open: vec![(f32::NEG_INFINITY, f32::INFINITY)],
+        ray: RayData {
+            ray_position: pos,
+            ray_direction: ray_dir,
+            local_origin: pos,
+            local_direction: ray_dir,
+            ..Default::default()
+        },
+    };
+
+    // the solids of the cell the ray is currently in and the next cell along the ray
+    let mut current = Vec::new();
+    let mut next = Vec::new();
+
+    if !columns.column(walker.cell, &mut current) {
+        return view.spans;
+    }
+    let mut current_thin = thin_walls(&mut current, walker.cell, walker.origin, walker.dir, (0., walker.exit_length()), false);
+
+    let mut distance = 0_f32;
+    let mut bounces = 0;
+    loop {
+        let exit_length = walker.exit_length();
+        view.ray.hit_cell = walker.cell;
+        view.ray.ray_length = distance;
+        view.ray.exit_length = exit_length;
+
+        // the floors and ceilings of the current cell from where the ray enters it to
+        // where it leaves, split up by the thin walls the ray hits along the way
+        let mut near = distance;
+        for (hit, solid) in &current_thin {
+            view.surfaces(&current, near, hit.distance);
+
+            view.ray.ray_length = hit.distance;
+            view.ray.collided_vertical = hit.collided_vertical;
+            view.ray.texture_u = hit.texture_u;
+            view.ray.normal = hit.normal;
+            view.faces(std::slice::from_ref(solid), &current, hit.distance);
+
+            near = hit.distance;
+        }
+        view.surfaces(&current, near, exit_length);
+
+        if view.is_closed() || exit_length >= max_distance {
+            break;
+        }
+
+        let collided_vertical;
+        (distance, collided_vertical) = walker.step();
+        let collided_vertical = walker.follow_portals(collided_vertical, |cell| columns.portal_at(cell));
+
+        // mirrors send the ray back into the cell it came from
+        if bounces < max_bounces && columns.mirror_at(walker.cell) {
+            let hit = ShapeHit {
+                distance,
+                texture_u: 0.,
+                collided_vertical,
+                normal: edge_normal(walker.dir, collided_vertical),
+            };
+            walker.bounce(&hit, CellShape::Block);
+            bounces += 1;
+
+            view.ray.bounces = bounces;
+            view.ray.local_origin = walker.origin;
+            view.ray.local_direction = walker.dir;
+
+            // the thin walls of the cell need to be found again for the new direction
+            columns.column(walker.cell, &mut current);
+            current_thin = thin_walls(&mut current, walker.cell, walker.origin, walker.dir, (distance, walker.exit_length()), collided_vertical);
+            continue;
+        }
+
+        if !columns.column(walker.cell, &mut next) {
+            // we are outside the map, break early
+            break;
+        }
+        let next_thin = thin_walls(&mut next, walker.cell, walker.origin, walker.dir, (distance, walker.exit_length()), collided_vertical);
+
+        view.ray.hit_cell = walker.cell;
+        view.ray.ray_length = distance;
+        view.ray.exit_length = walker.exit_length();
+        view.ray.collided_vertical = collided_vertical;
+        view.ray.texture_u = edge_u(walker.origin, walker.dir, distance, collided_vertical);
+        view.ray.normal = edge_normal(walker.dir, collided_vertical);
+        view.ray.transform = walker.transform;
+        view.ray.local_origin = walker.origin;
+        view.ray.local_direction = walker.dir;
+
+        // the faces of the cell the ray just entered
+        view.faces(&next, &current, distance);
+
+        std::mem::swap(&mut current, &mut next);
+        current_thin = next_thin;
+    }
+
+    view.spans.into_iter().map(|span| grid.to_world(span)).collect()
+}
+
+/// takes the thin walls out of the solids of a cell and returns the ones the
+/// ray hits along with where it hits them, ordered from nearest to farthest
+fn thin_walls(solids: &mut Vec<Solid>, cell: (i32, i32), pos: (f32, f32), dir: (f32, f32), span: (f32, f32), collided_vertical: bool) -> Vec<(ShapeHit, Solid)> {
+    let mut hits = Vec::new();
+    solids.retain(|solid| {
+        if solid.shape == CellShape::Block {
+            return true;
+        }
+        if let Some(hit) = solid.shape.intersect(cell, pos, dir, span, collided_vertical) {
+            hits.push((hit, *solid));
+        }
+        false
+    });
+    hits.sort_by(|a, b| a.0.distance.total_cmp(&b.0.distance));
+    hits
+}
+
+/// the spans found in a column so far along with which parts of the column can still be seen
+struct View {
+    spans: Vec<RayData>,
+
+    eye_height: f32,
+
+    /// the ranges of slopes (height above the camera over distance) that can still be
+    /// seen, anything outside of them is hidden behind something closer
+    open: Vec<(f32, f32)>,
+
+    /// holds the information shared by every span found at the current cell
+    ray: RayData,
+}
+
+impl View {
+    /// the slope from the camera to the given height at the given distance
+    fn slope(&self, height: f32, distance: f32) -> f32 {
+        if distance > 0. {
+            (height - self.eye_height) / distance
+        } else if height > self.eye_height {
+            f32::INFINITY
+        } else if height < self.eye_height {
+            f32::NEG_INFINITY
+        } else {
+            0.
+        }
+    }
+
+    /// true once everything behind the spans found so far is hidden
+    fn is_closed(&self) -> bool {
+        self.open.is_empty()
+    }
+
+    /// adds every part of a span between the given slopes that isnt hidden
+    fn push(&mut self, kind: SpanKind, layer: usize, val: u32, wall_span: (f32, f32), slopes: (f32, f32)) {
+        for i in 0..self.open.len() {
+            let view_slopes = (slopes.0.max(self.open[i].0), slopes.1.min(self.open[i].1));
+            if view_slopes.0 < view_slopes.1 {
+                self.spans.push(RayData {
+                    hit_val: Some(val),
+                    span_kind: kind,
+                    wall_span,
+                    view_slopes,
+                    layer,
+                    ..self.ray
+                });
+            }
+        }
+    }
+
+    /// hides everything between the given slopes
+    fn hide(&mut self, slopes: (f32, f32)) {
+        if slopes.0 >= slopes.1 {
+            return;
+        }
+
+        let mut open = Vec::with_capacity(self.open.len() + 1);
+        for &(low, high) in &self.open {
+            if high <= slopes.0 || low >= slopes.1 {
+                open.push((low, high));
+                continue;
+            }
+            if low < slopes.0 {
+                open.push((low, slopes.0));
+            }
+            if high > slopes.1 {
+                open.push((slopes.1, high));
+            }
+        }
+        self.open = open;
+    }
+
+    /// adds the sides of the solids facing the camera at the given distance, only
+    /// the parts that arent against a solid in the previous cell can be seen
+    fn faces(&mut self, solids: &[Solid], previous: &[Solid], distance: f32) {
+        for solid in solids {
+            let mut parts = vec![(solid.bottom, solid.top)];
+            for other in previous {
+                parts = parts
+                    .into_iter()
+                    .flat_map(|(bottom, top)| {
+                        if other.top <= bottom || other.bottom >= top {
+                            vec![(bottom, top)]
+                        } else {
+                            let mut left = Vec::new();
+                            if bottom < other.bottom {
+                                left.push((bottom, other.bottom));
+                            }
+                            if top > other.top {
+                                left.push((other.top, top));
+                            }
+                            left
+                        }
+                    })
+                    .collect();
+            }
+
+            for (bottom, top) in parts {
+                // walls report their whole height so textures line up, steps only report the part that sticks out
+                let wall_span = if solid.kind == SpanKind::Wall {
+                    (solid.bottom, solid.top)
+                } else {
+                    (bottom, top)
+                };
+                let slopes = (self.slope(bottom, distance), self.slope(top, distance));
+                self.push(solid.kind, solid.layer, solid.val, wall_span, slopes);
+            }
+        }
+
+        for solid in solids {
+            self.hide((self.slope(solid.bottom, distance), self.slope(solid.top, distance)));
+        }
+    }
+
+    /// adds the tops and bottoms of the solids that can be seen between the two distances
+    fn surfaces(&mut self, solids: &[Solid], near: f32, far: f32) {
+        // the surfaces closest to the camera height are in front of the ones farther from it
+        let mut order: Vec<&Solid> = solids.iter().collect();
+        order.sort_by(|a, b| {
+            let a = (a.top - self.eye_height).abs().min((a.bottom - self.eye_height).abs());
+            let b = (b.top - self.eye_height).abs().min((b.bottom - self.eye_height).abs());
+            a.total_cmp(&b)
+        });
+
+        for solid in order {
+            // floors can only be seen from above and ceilings from below
+            if solid.top < self.eye_height && solid.top.is_finite() {
+                let covered = solids.iter().any(|o| o.bottom <= solid.top && o.top > solid.top);
+                let slopes = (self.slope(solid.top, near), self.slope(solid.top, far));
+                if !covered {
+                    self.push(SpanKind::Floor, solid.top_layer, solid.top_val, (solid.top, solid.top), slopes);
+                }
+                self.hide(slopes);
+            }
+
+            if solid.bottom > self.eye_height && solid.bottom.is_finite() {
+                let covered = solids.iter().any(|o| o.bottom < solid.bottom && o.top >= solid.bottom);
+                let slopes = (self.slope(solid.bottom, far), self.slope(solid.bottom, near));
+                if !covered {
+                    self.push(SpanKind::Ceiling, solid.layer, solid.val, (solid.bottom, solid.bottom), slopes);
+                }
+                self.hide(slopes);
+            }
+        }
+
+        // hides everything inside of the solids
+        for solid in solids {
+            let bottom = self.slope(solid.bottom, near).min(self.slope(solid.bottom, far));
+            let top = self.slope(solid.top, near).max(self.slope(solid.top, far));
+            self.hide((bottom, top));
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{box_lengths, cast_multi, DdaWalker, Map, Object, ObjectShape, PortalTransform, RayCastEngine, RayHit};
+
+/// the smallest and largest cells an object covers
+type CellRange = ((i32, i32), (i32, i32));
+
+/// a grid holding the ids of the objects in each cell, so rays only have to test the objects in
+/// the cells they go through. the cells are usually the same as the cells of the map
+pub struct SpatialHash {
+    /// the width and height of each cell in world space and where the corner of cell (0, 0) is
+    cell_size: (f32, f32),
+    origin: (f32, f32),
+
+    cells: HashMap<(i32, i32), HashSet<usize>>,
+    objects: HashMap<usize, (ObjectShape, CellRange)>,
+
+    /// the smallest range of cells holding every object, rays stop looking once they leave it
+    bounds: Option<CellRange>,
+}
+
+impl SpatialHash {
+    /// creates an empty hash, use the same cell size and origin as the engine to line it up with the map
+    pub fn new(cell_size: (f32, f32), origin: (f32, f32)) -> Self {
+        Self {
+            cell_size,
+            origin,
+            cells: HashMap::new(),
+            objects: HashMap::new(),
+            bounds: None,
+        }
+    }
+
+    /// adds an object or moves it if there is already one with the same id, objects
+    /// that stay in the same cells only have their shape changed
+    pub fn insert(&mut self, object: Object) {
+        let range = self.range(&object.shape);
+        let old = self.objects.insert(object.id, (object.shape, range)).map(|(_, old)| old);
+        if old == Some(range) {
+            return;
+        }
+
+        if let Some(old) = old {
+            self.unlink(object.id, old);
+        }
+        for cell in cells(range) {
+            self.cells.entry(cell).or_default().insert(object.id);
+        }
+        self.bounds = Some(self.bounds.map_or(range, |bounds| union(bounds, range)));
+    }
+
+    /// takes an object out of the hash, returns None if it wasnt in it
+    pub fn remove(&mut self, id: usize) -> Option<Object> {
+        let (shape, range) = self.objects.remove(&id)?;
+        self.unlink(id, range);
+        Some(Object { id, shape })
+    }
+
+    pub fn get(&self, id: usize) -> Option<Object> {
+        self.objects.get(&id).map(|(shape, _)| Object { id, shape: *shape })
+    }
+
+    /// every object that covers some of the given cell
+    pub fn objects_in(&self, cell: (i32, i32)) -> impl Iterator<Item = Object> + '_ {
+        self.cells
+            .get(&cell)
+            .into_iter()
+            .flatten()
+            .map(|id| Object { id: *id, shape: self.objects[id].0 })
+    }
+
+    /// the cell a point in world space is in
+    pub fn cell_at(&self, pos: (f32, f32)) -> (i32, i32) {
+        (
+            ((pos.0 - self.origin.0) / self.cell_size.0).floor() as i32,
+            ((pos.1 - self.origin.1) / self.cell_size.1).floor() as i32,
+        )
+    }
+
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    /// walks a ray through the cells it crosses and returns the id, length and normal of the first object
+    /// it hits before `length`. objects with the same length are picked by the lowest id
+    pub(crate) fn nearest(&self, start: (f32, f32), dir: (f32, f32), length: f32) -> Option<(usize, f32, (f32, f32))> {
+        let ((min_x, min_y), (max_x, max_y)) = self.bounds?;
+
+        // walks the ray through the hash where each cell is 1 unit wide
+        let pos = ((start.0 - self.origin.0) / self.cell_size.0, (start.1 - self.origin.1) / self.cell_size.1);
+        let cell_dir = (dir.0 / self.cell_size.0, dir.1 / self.cell_size.1);
+        let scale = f32::sqrt(cell_dir.0 * cell_dir.0 + cell_dir.1 * cell_dir.1);
+        let cell_dir = (cell_dir.0 / scale, cell_dir.1 / scale);
+
+        // every object is inside the bounds so the ray can stop once it leaves them,
+        // otherwise it would keep walking through empty cells forever
+        let (_, far) = box_lengths(pos, cell_dir, (min_x as f32, min_y as f32), (max_x as f32 + 1., max_y as f32 + 1.))?;
+        let length = length.min(far / scale);
+        let mut walker = DdaWalker::new_in_cell(pos, cell_dir, self.cell_at(start), PortalTransform::default());
+
+        let mut best: Option<(usize, f32, (f32, f32))> = None;
+        loop {
+            for id in self.cells.get(&walker.cell).into_iter().flatten() {
+                if let Some((distance, normal)) = self.objects[id].0.intersect(start, dir, (0., length)) {
+                    if best.is_none_or(|(best_id, best_distance, _)| (distance, *id) < (best_distance, best_id)) {
+                        best = Some((*id, distance, normal));
+                    }
+                }
+            }
+
+            // objects can stick out into cells further along, so a hit only counts once
+            // every cell the ray went through before it has been looked at
+            let exit = walker.exit_length() / scale;
+            if best.is_some_and(|(_, distance, _)| distance <= exit) || exit >= length {
+                break;
+            }
+            walker.step();
+        }
+        best
+    }
+
+    fn range(&self, shape: &ObjectShape) -> CellRange {
+        let (min, max) = shape.bounds();
+        (self.cell_at(min), self.cell_at(max))
+    }
+
+    /// takes an object out of the cells in `range` and shrinks the bounds if it was on their edge
+    fn unlink(&mut self, id: usize, range: CellRange) {
+        for cell in cells(range) {
+            if let Some(ids) = self.cells.get_mut(&cell) {
+                ids.remove(&id);
+                if ids.is_empty() {
+                    self.cells.remove(&cell);
+                }
+            }
+        }
+
+        let ((min_x, min_y), (max_x, max_y)) = range;
+        if self.bounds.is_some_and(|((left, top), (right, bottom))| min_x == left || min_y == top || max_x == right || max_y == bottom) {
+            self.bounds = self.objects.values().map(|(_, range)| *range).reduce(union);
+        }
+    }
+}
+
+/// the smallest range holding both ranges
+fn union(a: CellRange, b: CellRange) -> CellRange {
+    ((a.0 .0.min(b.0 .0), a.0 .1.min(b.0 .1)), (a.1 .0.max(b.1 .0), a.1 .1.max(b.1 .1)))
+}
+
+/// every cell in a range
+fn cells(range: CellRange) -> impl Iterator<Item = (i32, i32)> {
+    let ((min_x, min_y), (max_x, max_y)) = range;
+    (min_y..=max_y).flat_map(move |y| (min_x..=max_x).map(move |x| (x, y)))
+}
+
+impl<T: Map + std::marker::Sync> RayCastEngine<T> {
+    /// same as `cast_ray_objects` but only tests the objects in the cells of the hash the ray goes through
+    pub fn cast_ray_spatial_hash(&self, pos: (f32, f32), angle: f32, max_distance: f32, hash: &SpatialHash) -> RayHit {
+        self.cast_ray_with_objects(pos, angle, max_distance, |start, dir, length| hash.nearest(start, dir, length))
+    }
+
+    /// same as `cast_rays_multi` but uses `cast_ray_spatial_hash` for each ray
+    pub fn cast_rays_multi_spatial_hash(&self, pos: (f32, f32), angles: Vec<f32>, max_distance: f32, hash: &SpatialHash) -> Vec<(usize, RayHit)> {
+        cast_multi(&angles, |angle| self.cast_ray_spatial_hash(pos, *angle, max_distance, hash))
+    }
+}
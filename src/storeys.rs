@@ -0,0 +1,128 @@
+use crate::spans::{block_mirror, build_column, cast_spans, Columns, Solid};
+use crate::{cast_multi, Map, Portal, RayCastEngine, RayData};
+
+/// a stack of maps, one for each storey of a level. each storey sits `storey_height`
+/// above the one below it and cells without a ceiling are open to the storey above
+pub struct Storeys<T: Map> {
+    /// the storeys from the bottom up, they should all be the same size
+    pub storeys: Vec<T>,
+    pub storey_height: f32,
+}
+
+impl<T: Map> Storeys<T> {
+    pub fn new(storeys: Vec<T>, storey_height: f32) -> Self {
+        Self { storeys, storey_height }
+    }
+
+    /// finds the storey something at the given height (measured from the bottom of the
+    /// lowest storey) in the given cell is standing in, returns the storey whose floor is
+    /// under it along with the height of that floor and the ceiling above it.
+    /// storeys without a floor in the cell are part of the storey below them
+    pub fn find_storey(&self, x: usize, y: usize, height: f32) -> (usize, f32, f32) {
+        let mut layer = 0;
+        let mut floor = f32::NEG_INFINITY;
+        let mut ceil = f32::INFINITY;
+        for (i, storey) in self.storeys.iter().enumerate() {
+            let base = i as f32 * self.storey_height;
+
+            // a storey only has a floor if the storey below it has a ceiling
+            if i == 0 || ceil.is_finite() {
+                let storey_floor = base + storey.get_floor_height(x, y);
+                if i > 0 && storey_floor > height {
+                    break;
+                }
+                layer = i;
+                floor = storey_floor;
+            }
+
+            ceil = base + storey.get_ceil_height(x, y);
+            if ceil > height {
+                break;
+            }
+        }
+
+        (layer, floor, ceil)
+    }
+}
+
+impl<T: Map + std::marker::Sync> RayCastEngine<Storeys<T>> {
+    /// same as `cast_ray` but only looks at the storey at `layer`. if there isnt a storey
+    /// at `layer` the ray doesnt go anywhere and doesnt hit anything
+    pub fn cast_ray_on_layer(&self, layer: usize, pos: (f32, f32), angle: f32, max_distance: f32) -> RayData {
+        let Some(storey) = self.map.storeys.get(layer) else {
+            let ray_dir = (angle.cos(), angle.sin());
+            return RayData {
+                ray_angle: angle,
+                ray_position: pos,
+                ray_direction: ray_dir,
+                local_origin: pos,
+                local_direction: ray_dir,
+                layer,
+                ..Default::default()
+            };
+        };
+        let engine = RayCastEngine {
+            map: storey,
+            map_size: self.map_size,
+            max_bounces: self.max_bounces,
+            cell_size: self.cell_size,
+            origin: self.origin,
+            distance_field: None,
+        };
+        RayData {
+            layer,
+            ..engine.cast_ray(pos, angle, max_distance)
+        }
+    }
+
+    /// same as `cast_rays_multi` but uses `cast_ray_on_layer` for each ray
+    pub fn cast_rays_multi_on_layer(&self, layer: usize, pos: (f32, f32), angles: Vec<f32>, max_distance: f32) -> Vec<(usize, RayData)> {
+        cast_multi(&angles, |angle| self.cast_ray_on_layer(layer, pos, *angle, max_distance))
+    }
+
+    /// same as `cast_ray_spans` but sees through open floors and ceilings into the other
+    /// storeys. the viewer is on the storey at `layer` and `eye_height` along with the
+    /// heights of the returned spans are measured from the bottom of that storey.
+    /// nothing can be seen from a storey that doesnt exist so no spans are returned
+    pub fn cast_ray_spans_on_layer(&self, layer: usize, pos: (f32, f32), angle: f32, max_distance: f32, eye_height: f32) -> Vec<RayData> {
+        if layer >= self.map.storeys.len() {
+            return Vec::new();
+        }
+        cast_spans(&self.grid_ray(pos, angle), max_distance, eye_height, self.max_bounces, &StoreyColumns { engine: self, layer })
+    }
+
+    /// same as `cast_rays_spans_multi` but uses `cast_ray_spans_on_layer` for each ray
+    pub fn cast_rays_spans_multi_on_layer(&self, layer: usize, pos: (f32, f32), angles: Vec<f32>, max_distance: f32, eye_height: f32) -> Vec<(usize, Vec<RayData>)> {
+        cast_multi(&angles, |angle| self.cast_ray_spans_on_layer(layer, pos, *angle, max_distance, eye_height))
+    }
+}
+
+/// the columns of every storey stacked on top of each other, seen from the storey at `layer`
+struct StoreyColumns<'a, T: Map> {
+    engine: &'a RayCastEngine<Storeys<T>>,
+    layer: usize,
+}
+
+impl<T: Map> Columns for StoreyColumns<'_, T> {
+    fn column(&self, cell: (i32, i32), solids: &mut Vec<Solid>) -> bool {
+        if !self.engine.in_bounds(cell) {
+            return false;
+        }
+        let storeys = &self.engine.map;
+        build_column(&storeys.storeys, storeys.storey_height, self.layer, (cell.0 as usize, cell.1 as usize), solids);
+        true
+    }
+
+    // only the portals and mirrors on the viewers storey are followed
+
+    fn portal_at(&self, cell: (i32, i32)) -> Option<Portal> {
+        if !self.engine.in_bounds(cell) {
+            return None;
+        }
+        self.engine.map.storeys[self.layer].get_portal(cell.0 as usize, cell.1 as usize)
+    }
+
+    fn mirror_at(&self, cell: (i32, i32)) -> bool {
+        self.engine.in_bounds(cell) && block_mirror(&self.engine.map.storeys[self.layer], (cell.0 as usize, cell.1 as usize))
+    }
+}
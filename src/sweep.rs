@@ -0,0 +1,115 @@
+use crate::{DdaWalker, Map, PortalTransform, RayCastEngine};
+
+/// how close to 0 a direction has to be along an axis for the box to go straight along the other one, so angles
+/// like `PI` that are only nearly along an axis as floats dont drag boxes into the walls they are sliding along
+const AXIS_TOLERANCE: f32 = 1e-6;
+
+/// where a box moving along a ray stopped
+#[derive(Default, Clone, Copy, PartialEq, Debug)]
+pub struct BoxHit {
+    /// how far the box moved before it touched a wall, left the map or got to the max distance
+    pub distance: f32,
+
+    /// where the middle of the box was when it stopped
+    pub position: (f32, f32),
+
+    /// the value in the wall cell the box touched, or None if it didnt touch one
+    pub hit_val: Option<u32>,
+
+    /// the wall cell the box touched, if it touched more than one at once this is one of them. this is the cell
+    /// outside of the map for boxes that got to the edge, and the cell the middle of the box is in for ones that got to the max distance
+    pub hit_cell: (i32, i32),
+
+    /// a unit vector pointing out of the face of the wall the box touched, back towards the box.
+    /// this is (0, 0) if the box started inside of the wall
+    pub normal: (f32, f32),
+}
+
+impl<T: Map + std::marker::Sync> RayCastEngine<T> {
+    /// moves a box `size` wide and tall in world space from `pos` at `angle` until it touches a wall, the box
+    /// stays lined up with the axes. unlike a ray it cant slip through gaps smaller than itself, like between
+    /// two walls that only touch at their corners, which makes it useful for checking if bodies and projectiles
+    /// fit. boxes can slide along walls they are only touching the side of. walls always fill their whole cell,
+    /// portals and mirrors are ignored and the box stops at the edge of the map the same as rays do
+    pub fn cast_box(&self, pos: (f32, f32), angle: f32, size: (f32, f32), max_distance: f32) -> BoxHit {
+        let grid = self.grid_ray(pos, angle);
+        let snap = |dir: f32| if dir.abs() < AXIS_TOLERANCE { 0. } else { dir };
+        let (pos, dir) = (grid.pos, (snap(grid.dir.0), snap(grid.dir.1)));
+        let max_distance = grid.length(max_distance);
+        let half = (size.0 / self.cell_size.0 / 2., size.1 / self.cell_size.1 / 2.);
+
+        // the edges of the box facing the way it goes cross cell edges whenever its front corner does
+        let front = (pos.0 + half.0.copysign(dir.0), pos.1 + half.1.copysign(dir.1));
+        let mut walker = DdaWalker::new_in_cell(front, dir, (front.0.floor() as i32, front.1.floor() as i32), PortalTransform::default());
+
+        let mut hit = BoxHit::default();
+        let mut stop = |distance: f32, cell: Option<(i32, i32)>, normal: (f32, f32)| {
+            let center = (pos.0 + dir.0 * distance, pos.1 + dir.1 * distance);
+            hit = BoxHit {
+                distance: distance / grid.scale,
+                position: self.to_world(center),
+                hit_val: cell.and_then(|cell| self.cell_at(cell)).filter(|val| *val > 0),
+                hit_cell: cell.unwrap_or((center.0.floor() as i32, center.1.floor() as i32)),
+                normal,
+            };
+        };
+
+        // the walls the box is already touching, it is only inside of ones it overlaps along both axes
+        let columns = span(pos.0, half.0, dir.0, walker.cell.0);
+        let rows = span(pos.1, half.1, dir.1, walker.cell.1);
+        for y in rows.0..=rows.1 {
+            for x in columns.0..=columns.1 {
+                if self.cell_at((x, y)).is_none_or(|val| val > 0) {
+                    let inside = (x as f32) < pos.0 + half.0 && (x + 1) as f32 > pos.0 - half.0;
+                    let normal = if !inside {
+                        (-walker.step.0 as f32, 0.)
+                    } else if (y as f32) < pos.1 + half.1 && (y + 1) as f32 > pos.1 - half.1 {
+                        (0., 0.)
+                    } else {
+                        (0., -walker.step.1 as f32)
+                    };
+                    stop(0., Some((x, y)), normal);
+                    return hit;
+                }
+            }
+        }
+
+        loop {
+            let (distance, vertical) = walker.step();
+            if distance > max_distance {
+                stop(max_distance, None, (0., 0.));
+                return hit;
+            }
+
+            // the new column or row of cells the front of the box just went into
+            let center = (pos.0 + dir.0 * distance, pos.1 + dir.1 * distance);
+            let (columns, rows, normal) = if vertical {
+                ((walker.cell.0, walker.cell.0), span(center.1, half.1, dir.1, walker.cell.1), (-walker.step.0 as f32, 0.))
+            } else {
+                (span(center.0, half.0, dir.0, walker.cell.0), (walker.cell.1, walker.cell.1), (0., -walker.step.1 as f32))
+            };
+
+            for y in rows.0..=rows.1 {
+                for x in columns.0..=columns.1 {
+                    // cells outside of the map stop the box without hitting anything
+                    if self.cell_at((x, y)).is_none_or(|val| val > 0) {
+                        stop(distance, Some((x, y)), normal);
+                        return hit;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// the first and last cells along one axis that a box from `center - half` to `center + half` is about to be in,
+/// given the cell the front of it is in. touching the edge of a cell only counts if the box is going into it
+fn span(center: f32, half: f32, dir: f32, front: i32) -> (i32, i32) {
+    if dir > 0. {
+        ((center - half).floor() as i32, front)
+    } else if dir < 0. {
+        (front, (center + half).ceil() as i32 - 1)
+    } else {
+        ((center - half).floor() as i32, (center + half).ceil() as i32 - 1)
+    }
+}
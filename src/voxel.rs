@@ -0,0 +1,193 @@
+use crate::cast_multi;
+
+/// trait to use to make your own custom structure for 3D voxel maps
+pub trait VoxelMap {
+    fn get_voxel(&self, x: usize, y: usize, z: usize) -> Option<u32>;
+    fn get_size(&self) -> (usize, usize, usize);
+}
+
+/// the face of a voxel a ray entered it through
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum VoxelFace {
+    #[default]
+    NegX,
+    PosX,
+    NegY,
+    PosY,
+    NegZ,
+    PosZ,
+}
+
+impl VoxelFace {
+    /// the unit vector pointing out of the face
+    pub fn normal(&self) -> (i32, i32, i32) {
+        match self {
+            VoxelFace::NegX => (-1, 0, 0),
+            VoxelFace::PosX => (1, 0, 0),
+            VoxelFace::NegY => (0, -1, 0),
+            VoxelFace::PosY => (0, 1, 0),
+            VoxelFace::NegZ => (0, 0, -1),
+            VoxelFace::PosZ => (0, 0, 1),
+        }
+    }
+}
+
+/// holds information useful when looking at a ray casted through a voxel map
+#[derive(Default, Clone, Copy)]
+pub struct VoxelRayData {
+    /// the length of the ray from the starting position to when it collided
+    pub ray_length: f32,
+
+    /// the value in the voxel the ray collided with, or None if the ray did not collide
+    pub hit_val: Option<u32>,
+
+    /// the voxel the ray collided with
+    pub hit_voxel: (i32, i32, i32),
+
+    /// the face of the voxel the ray collided with
+    pub face: VoxelFace,
+
+    /// the starting position of the ray
+    pub ray_position: (f32, f32, f32),
+
+    /// a unit vector for the direction the ray traveled
+    pub ray_direction: (f32, f32, f32),
+}
+
+impl VoxelRayData {
+    /// the point the ray collided at
+    pub fn hit_point(&self) -> (f32, f32, f32) {
+        (
+            self.ray_position.0 + self.ray_direction.0 * self.ray_length,
+            self.ray_position.1 + self.ray_direction.1 * self.ray_length,
+            self.ray_position.2 + self.ray_direction.2 * self.ray_length,
+        )
+    }
+
+    /// the empty voxel in front of the face that was hit, useful for placing voxels when picking
+    pub fn adjacent_voxel(&self) -> (i32, i32, i32) {
+        let normal = self.face.normal();
+        (
+            self.hit_voxel.0 + normal.0,
+            self.hit_voxel.1 + normal.1,
+            self.hit_voxel.2 + normal.2,
+        )
+    }
+}
+
+/// ray cast engine to hold a voxel map and allow the user to cast rays in any 3D direction
+pub struct RayCastEngine3D<T: VoxelMap> {
+    pub map: T,
+    pub map_size: (usize, usize, usize),
+}
+
+impl<T: VoxelMap + std::marker::Sync> RayCastEngine3D<T> {
+    /// creates a new engine with the provided voxel map
+    pub fn new(map: T, map_size: (usize, usize, usize)) -> Self {
+        Self { map, map_size }
+    }
+
+    /// takes a vector of directions for rays to be casted in and
+    /// uses rayon to cast them all with multithreading, the results
+    /// are returned along with their directions index
+    pub fn cast_rays_multi(&self, pos: (f32, f32, f32), directions: Vec<(f32, f32, f32)>, max_distance: f32) -> Vec<(usize, VoxelRayData)> {
+        cast_multi(&directions, |direction| self.cast_ray(pos, *direction, max_distance))
+    }
+
+    /// casts a single ray from the given position in the given direction using the
+    /// Amanatides-Woo voxel traversal, the direction doesnt need to be normalized
+    pub fn cast_ray(&self, pos: (f32, f32, f32), direction: (f32, f32, f32), max_distance: f32) -> VoxelRayData {
+        let length = f32::sqrt(direction.0 * direction.0 + direction.1 * direction.1 + direction.2 * direction.2);
+        let ray_dir = (direction.0 / length, direction.1 / length, direction.2 / length);
+
+        let mut ray = VoxelRayData {
+            ray_position: pos,
+            ray_direction: ray_dir,
+            ..Default::default()
+        };
+
+        // a zero length direction cant go anywhere
+        if length == 0. || length.is_nan() {
+            return ray;
+        }
+
+        let pos = [pos.0, pos.1, pos.2];
+        let dir = [ray_dir.0, ray_dir.1, ray_dir.2];
+
+        let mut voxel = [pos[0].floor() as i32, pos[1].floor() as i32, pos[2].floor() as i32];
+        let mut step = [0; 3];
+
+        // the length of the ray needed to cross one voxel along each axis
+        let mut t_delta = [f32::INFINITY; 3];
+
+        // the length of the ray when it next crosses a voxel edge along each axis
+        let mut t_max = [f32::INFINITY; 3];
+
+        for axis in 0..3 {
+            if dir[axis] > 0. {
+                step[axis] = 1;
+                t_delta[axis] = 1. / dir[axis];
+                t_max[axis] = ((voxel[axis] + 1) as f32 - pos[axis]) * t_delta[axis];
+            } else if dir[axis] < 0. {
+                step[axis] = -1;
+                t_delta[axis] = -1. / dir[axis];
+                t_max[axis] = (pos[axis] - voxel[axis] as f32) * t_delta[axis];
+            }
+        }
+
+        let mut distance = 0_f32;
+        while distance < max_distance {
+            // steps along whichever axis reaches its next voxel edge first
+            let axis = if t_max[0] < t_max[1] {
+                if t_max[0] < t_max[2] { 0 } else { 2 }
+            } else if t_max[1] < t_max[2] {
+                1
+            } else {
+                2
+            };
+
+            voxel[axis] += step[axis];
+            distance = t_max[axis];
+            t_max[axis] += t_delta[axis];
+
+            // the ray enters through the face pointing back towards where it came from
+            ray.face = match (axis, step[axis] > 0) {
+                (0, true) => VoxelFace::NegX,
+                (0, false) => VoxelFace::PosX,
+                (1, true) => VoxelFace::NegY,
+                (1, false) => VoxelFace::PosY,
+                (_, true) => VoxelFace::NegZ,
+                (_, false) => VoxelFace::PosZ,
+            };
+            ray.ray_length = distance;
+            ray.hit_voxel = (voxel[0], voxel[1], voxel[2]);
+
+            match self.voxel_at(voxel) {
+                Some(val) if val > 0 => {
+                    ray.hit_val = Some(val);
+                    break;
+                }
+                Some(_) => {}
+                // we are outside the map, break early
+                None => break,
+            }
+        }
+
+        ray
+    }
+
+    /// gets the value of a voxel, or None if the voxel is outside of the map
+    fn voxel_at(&self, voxel: [i32; 3]) -> Option<u32> {
+        if voxel[0] >= 0
+            && voxel[0] < self.map_size.0 as i32
+            && voxel[1] >= 0
+            && voxel[1] < self.map_size.1 as i32
+            && voxel[2] >= 0
+            && voxel[2] < self.map_size.2 as i32
+        {
+            self.map.get_voxel(voxel[0] as usize, voxel[1] as usize, voxel[2] as usize)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,45 @@
+mod common;
+
+use common::{Grid, Rng};
+use raycast_dda::{Portal, RayCastEngine};
+
+const MAP_SIZE: (usize, usize) = (96, 80);
+
+#[test]
+fn skipping_empty_space_hits_the_same_walls() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+
+    for density in [0., 0.002, 0.02, 0.1] {
+        let mut grid = Grid::room(MAP_SIZE);
+        for y in 1..MAP_SIZE.1 - 1 {
+            for x in 1..MAP_SIZE.0 - 1 {
+                if rng.next() < density {
+                    grid.set(x, y, 1 + (rng.next() * 3.) as u32);
+                }
+            }
+        }
+        // portals and mirrors stop the skipping too
+        grid.set_portal(40, 30, Portal { target: (70, 60), rotation: 1 });
+        grid.set(20, 50, 5);
+        grid.set_mirror(20, 50);
+
+        let mut engine = RayCastEngine::new(grid, MAP_SIZE);
+        let rays: Vec<((f32, f32), f32, f32)> = (0..2000)
+            .map(|_| {
+                let pos = (1. + rng.next() * (MAP_SIZE.0 - 2) as f32, 1. + rng.next() * (MAP_SIZE.1 - 2) as f32);
+                (pos, rng.next() * std::f32::consts::TAU, rng.next() * 150.)
+            })
+            .collect();
+
+        let plain: Vec<_> = rays.iter().map(|(pos, angle, max)| engine.cast_ray(*pos, *angle, *max)).collect();
+        engine.build_distance_field();
+
+        // skipping has to land on exactly the same hits as walking every cell, even when it runs out of distance
+        for (ray, plain) in rays.iter().zip(plain) {
+            let field = engine.cast_ray(ray.0, ray.1, ray.2);
+            assert_eq!(field.ray_length.to_bits(), plain.ray_length.to_bits(), "{density} {ray:?}");
+            assert_eq!((field.hit_cell, field.hit_val, field.bounces), (plain.hit_cell, plain.hit_val, plain.bounces), "{density} {ray:?}");
+            assert_eq!((field.texture_u.to_bits(), field.normal), (plain.texture_u.to_bits(), plain.normal), "{density} {ray:?}");
+        }
+    }
+}
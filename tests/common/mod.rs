@@ -0,0 +1,154 @@
+// each test file only uses some of the helpers
+#![allow(dead_code)]
+
+use raycast_dda::{CellShape, Map, Portal};
+
+/// a map stored as a vector of cells, along with the height of the wall, floor and ceiling in each cell
+/// and the shape of the walls. cells can also be portals, and walls can be mirrors
+pub struct Grid {
+    pub size: (usize, usize),
+    pub cells: Vec<u32>,
+    pub heights: Vec<f32>,
+    pub floors: Vec<f32>,
+    pub ceilings: Vec<f32>,
+    pub shapes: Vec<CellShape>,
+    pub portals: Vec<Option<Portal>>,
+    pub mirrors: Vec<bool>,
+}
+
+impl Map for Grid {
+    fn get_cell(&self, x: usize, y: usize) -> Option<u32> {
+        if x >= self.size.0 || y >= self.size.1 {
+            return None;
+        }
+        Some(self.cells[y * self.size.0 + x])
+    }
+
+    fn get_size(&self) -> (usize, usize) {
+        self.size
+    }
+
+    fn get_height(&self, x: usize, y: usize) -> f32 {
+        self.heights[y * self.size.0 + x]
+    }
+
+    fn get_floor_height(&self, x: usize, y: usize) -> f32 {
+        self.floors[y * self.size.0 + x]
+    }
+
+    fn get_ceil_height(&self, x: usize, y: usize) -> f32 {
+        self.ceilings[y * self.size.0 + x]
+    }
+
+    fn get_shape(&self, x: usize, y: usize) -> CellShape {
+        self.shapes[y * self.size.0 + x]
+    }
+
+    fn get_portal(&self, x: usize, y: usize) -> Option<Portal> {
+        self.portals[y * self.size.0 + x]
+    }
+
+    fn is_mirror(&self, x: usize, y: usize) -> bool {
+        self.mirrors[y * self.size.0 + x]
+    }
+}
+
+impl Grid {
+    /// a map without any walls
+    pub fn new(size: (usize, usize)) -> Self {
+        Grid {
+            size,
+            cells: vec![0; size.0 * size.1],
+            heights: vec![1.; size.0 * size.1],
+            floors: vec![0.; size.0 * size.1],
+            ceilings: vec![f32::INFINITY; size.0 * size.1],
+            shapes: vec![CellShape::Block; size.0 * size.1],
+            portals: vec![None; size.0 * size.1],
+            mirrors: vec![false; size.0 * size.1],
+        }
+    }
+
+    /// an empty room with walls around the edge
+    pub fn room(size: (usize, usize)) -> Self {
+        let mut grid = Grid::new(size);
+        for y in 0..size.1 {
+            for x in 0..size.0 {
+                if x == 0 || y == 0 || x == size.0 - 1 || y == size.1 - 1 {
+                    grid.set(x, y, 1);
+                }
+            }
+        }
+        grid
+    }
+
+    /// walls around the edge with a few blocks scattered around inside, each with its own value
+    pub fn scattered(size: (usize, usize)) -> Self {
+        let mut grid = Grid::new(size);
+        for y in 0..size.1 {
+            for x in 0..size.0 {
+                if x == 0 || y == 0 || x == size.0 - 1 || y == size.1 - 1 || (x * 7 + y * 13).is_multiple_of(11) {
+                    grid.set(x, y, 1 + (x + y) as u32 % 4);
+                }
+            }
+        }
+        grid
+    }
+
+    /// walls around the edge and a wall in roughly `density` of the other cells
+    pub fn random(size: (usize, usize), density: f32, rng: &mut Rng) -> Self {
+        let mut grid = Grid::room(size);
+        for y in 1..size.1 - 1 {
+            for x in 1..size.0 - 1 {
+                if rng.next() < density {
+                    grid.set(x, y, 1);
+                }
+            }
+        }
+        grid
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, val: u32) {
+        self.cells[y * self.size.0 + x] = val;
+    }
+
+    pub fn set_height(&mut self, x: usize, y: usize, height: f32) {
+        self.heights[y * self.size.0 + x] = height;
+    }
+
+    pub fn set_floor(&mut self, x: usize, y: usize, height: f32) {
+        self.floors[y * self.size.0 + x] = height;
+    }
+
+    pub fn set_ceiling(&mut self, x: usize, y: usize, height: f32) {
+        self.ceilings[y * self.size.0 + x] = height;
+    }
+
+    pub fn set_shape(&mut self, x: usize, y: usize, shape: CellShape) {
+        self.shapes[y * self.size.0 + x] = shape;
+    }
+
+    pub fn set_portal(&mut self, x: usize, y: usize, portal: Portal) {
+        self.portals[y * self.size.0 + x] = Some(portal);
+    }
+
+    pub fn set_mirror(&mut self, x: usize, y: usize) {
+        self.mirrors[y * self.size.0 + x] = true;
+    }
+}
+
+/// checks two floats are within `tolerance` of each other
+pub fn assert_near(value: f32, expected: f32, tolerance: f32) {
+    assert!((value - expected).abs() <= tolerance, "{value} isnt {expected}");
+}
+
+/// a small xorshift generator so every run uses the same maps
+pub struct Rng(pub u64);
+
+impl Rng {
+    pub fn next(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
@@ -0,0 +1,175 @@
+mod common;
+
+use common::{Grid, Rng};
+use raycast_dda::{ConeCast, RayCastEngine};
+use std::f32::consts::TAU;
+
+const MAP_SIZE: (usize, usize) = (24, 17);
+
+/// a room with a few pillars and walls with different values
+fn make_map(rng: &mut Rng) -> Grid {
+    let mut grid = Grid::room(MAP_SIZE);
+    for y in 1..MAP_SIZE.1 - 1 {
+        for x in 1..MAP_SIZE.0 - 1 {
+            if rng.next() < 0.12 {
+                grid.set(x, y, 1 + (rng.next() * 3.) as u32);
+            }
+        }
+    }
+    grid
+}
+
+/// checks the cone against lots of rays casted across it, every cell a ray goes through and every face
+/// it hits has to be in the cone, and a ray through the middle of every part of a face has to hit it
+fn assert_matches_rays(engine: &RayCastEngine<Grid>, cone: &ConeCast, pos: (f32, f32), angle: f32, arc: f32, max_distance: f32) {
+    let cell_size = engine.cell_size;
+    let rays = 3000;
+    for i in 0..rays {
+        let ray_angle = angle - arc / 2. + arc * (i as f32 + 0.5) / rays as f32;
+        let ray = engine.cast_ray(pos, ray_angle, max_distance);
+        let length = ray.ray_length.min(max_distance);
+
+        let mut along = 0.;
+        while along < length - 1e-3 {
+            let cell = engine.cell_at_point((pos.0 + ray_angle.cos() * along, pos.1 + ray_angle.sin() * along));
+            assert!(cone.cells.iter().any(|c| c.cell == cell), "{cell:?} at {along} along {ray_angle} isnt in the cone");
+            along += 0.05;
+        }
+
+        if let Some(val) = ray.hit_val.filter(|_| ray.ray_length < max_distance - 1e-3) {
+            // hits right on a corner could be on either face
+            let hit = (pos.0 + ray_angle.cos() * ray.ray_length, pos.1 + ray_angle.sin() * ray.ray_length);
+            let grid = engine.to_grid(hit);
+            if (grid.0 - grid.0.round()).abs() < 1e-3 && (grid.1 - grid.1.round()).abs() < 1e-3 {
+                continue;
+            }
+
+            let face = cone.faces.iter().find(|face| {
+                let normal = (face.normal.0 - ray.normal.0).abs() < 1e-5 && (face.normal.1 - ray.normal.1).abs() < 1e-5;
+                face.cell == ray.hit_cell && normal && face.angles.0 - 1e-4 <= ray_angle && ray_angle <= face.angles.1 + 1e-4
+            });
+            let face = face.unwrap_or_else(|| panic!("the hit on {:?} along {ray_angle} isnt in the cone", ray.hit_cell));
+            assert_eq!(face.val, val);
+
+            let cell = cone.cells.iter().find(|c| c.cell == ray.hit_cell).unwrap();
+            assert!(cell.distance <= ray.ray_length + 1e-3, "{cell:?} is farther than {}", ray.ray_length);
+        }
+    }
+
+    for face in &cone.faces {
+        if face.angles.1 - face.angles.0 < 1e-3 {
+            continue;
+        }
+        let middle = (face.angles.0 + face.angles.1) / 2.;
+        let ray = engine.cast_ray(pos, middle, max_distance + 1.);
+        assert_eq!(ray.hit_cell, face.cell, "{face:?}");
+
+        // the ends of the face are along their angles and on the face
+        for (point, angle) in [(face.points.0, face.angles.0), (face.points.1, face.angles.1)] {
+            let length = ((point.0 - pos.0).powi(2) + (point.1 - pos.1).powi(2)).sqrt();
+            assert!(length <= max_distance + 1e-4, "{face:?} is out of range");
+            assert!((pos.0 + angle.cos() * length - point.0).abs() < 1e-3 && (pos.1 + angle.sin() * length - point.1).abs() < 1e-3);
+
+            let on_edge = if face.normal.0 != 0. {
+                (point.0 / cell_size.0 - (face.cell.0 as f32 + (face.normal.0 > 0.) as i32 as f32)).abs()
+            } else {
+                (point.1 / cell_size.1 - (face.cell.1 as f32 + (face.normal.1 > 0.) as i32 as f32)).abs()
+            };
+            assert!(on_edge < 1e-3, "{face:?} isnt on the edge of its cell");
+        }
+    }
+}
+
+#[test]
+fn cones_match_rays() {
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+    for _ in 0..4 {
+        let grid = make_map(&mut rng);
+        let mut engine = RayCastEngine::new(grid, MAP_SIZE);
+
+        for cell_size in [(1., 1.), (1.5, 0.75)] {
+            engine.cell_size = cell_size;
+            for _ in 0..6 {
+                // the apex can be inside of a wall, which doesnt hide anything
+                let pos = (
+                    (1. + rng.next() * (MAP_SIZE.0 - 2) as f32) * cell_size.0,
+                    (1. + rng.next() * (MAP_SIZE.1 - 2) as f32) * cell_size.1,
+                );
+                let (angle, arc) = (rng.next() * 20. - 10., rng.next() * 4. + 0.1);
+                let max_distance = 3. + rng.next() * 20.;
+
+                let cone = engine.cast_cone(pos, angle, arc, max_distance);
+                assert_matches_rays(&engine, &cone, pos, angle, arc, max_distance);
+            }
+        }
+    }
+}
+
+#[test]
+fn whole_turns_see_all_the_way_around() {
+    let mut rng = Rng(7);
+    let engine = RayCastEngine::new(make_map(&mut rng), MAP_SIZE);
+    let pos = (11.3, 8.6);
+
+    let cone = engine.cast_cone(pos, 1., TAU, 30.);
+    assert_matches_rays(&engine, &cone, pos, 1., TAU, 30.);
+}
+
+#[test]
+fn pillars_hide_part_of_a_wall() {
+    let mut grid = Grid::room(MAP_SIZE);
+    grid.set(4, 4, 2);
+    for y in 1..MAP_SIZE.1 - 1 {
+        grid.set(8, y, 1);
+    }
+    let engine = RayCastEngine::new(grid, MAP_SIZE);
+
+    let pos = (1.5, 4.5);
+    let cone = engine.cast_cone(pos, 0., 1.2, 40.);
+
+    // the front of the pillar is seen as one face even though it crosses from one quarter into the next
+    let shadow = 0.2f32.atan();
+    let pillar: Vec<_> = cone.faces.iter().filter(|face| face.cell == (4, 4)).collect();
+    assert_eq!(pillar.len(), 1);
+    assert_eq!(pillar[0].normal, (-1., 0.));
+    assert!((pillar[0].angles.0 + shadow).abs() < 1e-5 && (pillar[0].angles.1 - shadow).abs() < 1e-5);
+
+    // the shadow of the pillar goes from 3.2 to 5.8 on the far wall, where the cell in the middle is hidden
+    let far_wall = 8;
+    assert!(!cone.cells.iter().any(|cell| cell.cell == (far_wall, 4)));
+    assert!(cone.cells.iter().any(|cell| cell.cell == (4, 4)));
+
+    let above = cone.faces.iter().find(|face| face.cell == (far_wall, 3)).unwrap();
+    assert!((above.angles.1 + shadow).abs() < 1e-5);
+    assert!((above.points.1.0 - far_wall as f32).abs() < 1e-4 && (above.points.1.1 - (4.5 - (far_wall as f32 - 1.5) * 0.2)).abs() < 1e-4);
+
+    let below = cone.faces.iter().find(|face| face.cell == (far_wall, 5)).unwrap();
+    assert!((below.angles.0 - shadow).abs() < 1e-5);
+}
+
+#[test]
+fn max_distance_cuts_off_the_cone() {
+    let engine = RayCastEngine::new(Grid::room(MAP_SIZE), MAP_SIZE);
+    let pos = (12.5, 8.5);
+    let cone = engine.cast_cone(pos, 0., TAU, 3.);
+
+    // none of the walls are in range and nothing is in the way of any of the cells in range
+    assert!(cone.faces.is_empty());
+    for y in 0..MAP_SIZE.1 as i32 {
+        for x in 0..MAP_SIZE.0 as i32 {
+            let dx = (x as f32 - pos.0).max(pos.0 - (x + 1) as f32).max(0.);
+            let dy = (y as f32 - pos.1).max(pos.1 - (y + 1) as f32).max(0.);
+            let nearest = (dx * dx + dy * dy).sqrt();
+
+            match cone.cells.iter().find(|cell| cell.cell == (x, y)) {
+                Some(cell) => assert!((cell.distance - nearest).abs() < 1e-5 && cell.val == 0, "{cell:?} is {nearest} away"),
+                None => assert!(nearest > 3., "({x}, {y}) is {nearest} away"),
+            }
+        }
+    }
+
+    // the nearest cells come first, starting with the one the apex is in
+    assert_eq!(cone.cells[0].cell, (12, 8));
+    assert_eq!(cone.cells[0].distance, 0.);
+    assert!(cone.cells.windows(2).all(|pair| pair[0].distance <= pair[1].distance));
+}
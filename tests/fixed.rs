@@ -0,0 +1,203 @@
+#![cfg(feature = "fixed")]
+
+mod common;
+
+use common::Grid;
+use raycast_dda::{CellShape, Fixed, Portal, RayCastEngine, RayData};
+
+const MAP_SIZE: (usize, usize) = (16, 16);
+
+/// the scattered map with the cell the rays start in cleared out
+fn make_map() -> Grid {
+    let mut grid = Grid::scattered(MAP_SIZE);
+    grid.set(8, 8, 0);
+    grid
+}
+
+/// every angle around a circle in small steps, including the ones right along the axes
+fn angles() -> Vec<Fixed> {
+    (0..720).map(|i| Fixed(i * Fixed::TAU.0 / 720)).collect()
+}
+
+/// mixes every number of a ray that comes from fixed point maths into one hash
+fn hash_ray(hash: u64, ray: &RayData) -> u64 {
+    let values = [
+        ray.ray_length.to_bits(),
+        ray.exit_length.to_bits(),
+        ray.texture_u.to_bits(),
+        ray.ray_direction.0.to_bits(),
+        ray.ray_direction.1.to_bits(),
+        ray.hit_cell.0 as u32,
+        ray.hit_cell.1 as u32,
+        ray.hit_val.unwrap_or(u32::MAX),
+        ray.collided_vertical as u32,
+    ];
+
+    // fnv-1a
+    values.iter().flat_map(|value| value.to_le_bytes()).fold(hash, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+fn hash_rays<'a>(rays: impl Iterator<Item = &'a RayData>) -> u64 {
+    rays.fold(0xcbf29ce484222325, hash_ray)
+}
+
+#[test]
+fn sin_cos_is_close_to_floats() {
+    for i in -2000..2000 {
+        let angle = Fixed(i * 347);
+        let (sin, cos) = angle.sin_cos();
+        let real = (angle.0 as f64 / 65536.).sin_cos();
+        assert!((sin.0 as f64 / 65536. - real.0).abs() < 3. / 65536., "sin of {angle:?}");
+        assert!((cos.0 as f64 / 65536. - real.1).abs() < 3. / 65536., "cos of {angle:?}");
+    }
+}
+
+#[test]
+fn arithmetic_rounds_down() {
+    assert_eq!(Fixed::from_int(3) * Fixed::from_f32(0.5), Fixed::from_f32(1.5));
+    assert_eq!(Fixed::ONE / Fixed::from_int(3), Fixed(21845));
+    assert_eq!(-Fixed::ONE / Fixed::from_int(3), Fixed(-21846));
+    assert_eq!(Fixed::from_f32(-1.25).floor(), -2);
+    assert_eq!(Fixed::from_f32(-1.25).fract(), Fixed::from_f32(0.75));
+    assert_eq!(Fixed::from_f32(2.75).to_f32(), 2.75);
+}
+
+#[test]
+fn arithmetic_out_of_range_wraps() {
+    assert_eq!(Fixed(i32::MAX) + Fixed(1), Fixed(i32::MIN));
+    assert_eq!(Fixed(i32::MIN) - Fixed(1), Fixed(i32::MAX));
+    assert_eq!(-Fixed(i32::MIN), Fixed(i32::MIN));
+    assert_eq!(Fixed(i32::MIN).abs(), Fixed(i32::MIN));
+    assert_eq!(Fixed::from_int(200) * Fixed::from_int(200), Fixed::from_int(40000 - 65536));
+
+    // integers only fit from -32768 to 32767
+    assert_eq!(Fixed::from_int(32767).to_f32(), 32767.);
+    assert_eq!(Fixed::from_int(-32768).to_f32(), -32768.);
+    assert_eq!(Fixed::from_int(32768), Fixed(i32::MIN));
+
+    // dividing saturates instead
+    assert_eq!(Fixed::from_int(30000) / Fixed(1), Fixed(i32::MAX));
+    assert_eq!(Fixed::from_int(-30000) / Fixed(1), Fixed(i32::MIN));
+    assert_eq!(Fixed::ONE / Fixed::ZERO, Fixed(i32::MAX));
+    assert_eq!(-Fixed::ONE / Fixed::ZERO, Fixed(i32::MIN));
+    assert_eq!(Fixed::ZERO / Fixed::ZERO, Fixed::ZERO);
+}
+
+#[test]
+fn casts_are_the_same_every_time() {
+    let engine = RayCastEngine::new(make_map(), MAP_SIZE);
+    let pos = (Fixed::from_f32(8.3), Fixed::from_f32(8.6));
+    let max_distance = Fixed::from_int(32);
+
+    let rays: Vec<RayData> = angles().into_iter().map(|angle| engine.cast_ray_fixed(pos, angle, max_distance)).collect();
+    let mut multi = engine.cast_rays_fixed_multi(pos, angles(), max_distance);
+    multi.sort_by_key(|(i, _)| *i);
+
+    assert_eq!(hash_rays(rays.iter()), hash_rays(multi.iter().map(|(_, ray)| ray)));
+}
+
+#[test]
+fn casts_match_known_results() {
+    // these were worked out once and have to come out exactly the same on any machine
+    let engine = RayCastEngine::new(make_map(), MAP_SIZE);
+    let max_distance = Fixed::from_int(32);
+    let rays: Vec<RayData> = [(8.3, 8.6), (1.5, 1.5), (14.999, 7.0)]
+        .iter()
+        .flat_map(|pos| {
+            let pos = (Fixed::from_f32(pos.0), Fixed::from_f32(pos.1));
+            angles().into_iter().map(move |angle| (pos, angle))
+        })
+        .map(|(pos, angle)| engine.cast_ray_fixed(pos, angle, max_distance))
+        .collect();
+
+    assert_eq!(hash_rays(rays.iter()), 6216359342827345846);
+}
+
+#[test]
+fn casts_agree_with_floats() {
+    let engine = RayCastEngine::new(make_map(), MAP_SIZE);
+    let pos = (Fixed::from_f32(8.3), Fixed::from_f32(8.6));
+
+    let mut matched = 0;
+    for angle in angles() {
+        let fixed = engine.cast_ray_fixed(pos, angle, Fixed::from_int(32));
+        let float = engine.cast_ray((pos.0.to_f32(), pos.1.to_f32()), angle.to_f32(), 32.);
+
+        // rays that go right past a corner can go either side of it with the tiny differences in direction
+        if (fixed.ray_length - float.ray_length).abs() > 0.01 {
+            continue;
+        }
+        assert_eq!(fixed.hit_val, float.hit_val);
+        assert_eq!(fixed.hit_cell, float.hit_cell);
+        assert_eq!(fixed.collided_vertical, float.collided_vertical);
+        assert!((fixed.texture_u - float.texture_u).abs() < 0.01);
+        assert!((fixed.exit_length - float.exit_length).abs() < 0.01);
+        assert_eq!(fixed.normal, float.normal);
+        matched += 1;
+    }
+    assert!(matched > angles().len() * 9 / 10);
+}
+
+#[test]
+fn casts_use_world_space() {
+    let mut engine = RayCastEngine::new(make_map(), MAP_SIZE);
+    engine.cell_size = (2., 0.5);
+    engine.origin = (-4., 3.);
+
+    let pos = (Fixed::from_f32(13.), Fixed::from_f32(7.25));
+    let mut matched = 0;
+    for angle in angles() {
+        let fixed = engine.cast_ray_fixed(pos, angle, Fixed::from_int(64));
+        let float = engine.cast_ray((pos.0.to_f32(), pos.1.to_f32()), angle.to_f32(), 64.);
+        if (fixed.ray_length - float.ray_length).abs() > 0.01 {
+            continue;
+        }
+        assert_eq!(fixed.hit_cell, float.hit_cell);
+        assert!((fixed.texture_u - float.texture_u).abs() < 0.01);
+        matched += 1;
+    }
+    assert!(matched > angles().len() * 9 / 10);
+}
+
+#[test]
+fn casts_ignore_shapes_portals_and_mirrors() {
+    let mut grid = Grid::room(MAP_SIZE);
+    grid.set(6, 5, 1);
+    grid.set_shape(6, 5, CellShape::Thin { vertical: true, offset: 0.5 });
+    grid.set_portal(5, 7, Portal { target: (10, 2), rotation: 1 });
+    grid.set(9, 3, 2);
+    grid.set_mirror(9, 3);
+    let engine = RayCastEngine::new(grid, MAP_SIZE);
+    let cast = |pos: (f32, f32)| {
+        let fixed = engine.cast_ray_fixed((Fixed::from_f32(pos.0), Fixed::from_f32(pos.1)), Fixed::ZERO, Fixed::from_int(32));
+        (fixed, engine.cast_ray(pos, 0., 32.))
+    };
+
+    // thin walls are hit at the edge of their cell instead of in the middle
+    let (fixed, float) = cast((2.5, 5.5));
+    assert_eq!((fixed.hit_cell, fixed.ray_length), ((6, 5), 3.5));
+    assert_eq!((float.hit_cell, float.ray_length), ((6, 5), 4.));
+
+    // rays go straight through portals instead of coming out of the top of (10, 2)
+    let (fixed, float) = cast((2.5, 7.5));
+    assert_eq!((fixed.hit_cell, fixed.ray_length), ((15, 7), 12.5));
+    assert_eq!((float.hit_cell, float.ray_length, float.transform.rotation), ((10, 15), 15.5, 1));
+
+    // mirrors are plain walls instead of bouncing the ray back to the other side of the room
+    let (fixed, float) = cast((2.5, 3.5));
+    assert_eq!((fixed.hit_cell, fixed.hit_val, fixed.ray_length, fixed.bounces), ((9, 3), Some(2), 6.5, 0));
+    assert_eq!((float.hit_cell, float.ray_length, float.bounces), ((0, 3), 14.5, 1));
+}
+
+#[test]
+fn casts_with_a_zero_cell_size_dont_panic() {
+    let mut engine = RayCastEngine::new(make_map(), MAP_SIZE);
+    let pos = (Fixed::from_f32(8.3), Fixed::from_f32(8.6));
+    for cell_size in [(0., 1.), (1., 0.), (0., 0.), (1e-9, -1.)] {
+        engine.cell_size = cell_size;
+        for angle in angles().into_iter().step_by(45) {
+            let ray = engine.cast_ray_fixed(pos, angle, Fixed::from_int(32));
+            assert!(ray.ray_length.is_finite() && ray.texture_u.is_finite(), "{cell_size:?} {angle:?}");
+        }
+    }
+}
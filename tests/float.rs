@@ -0,0 +1,71 @@
+mod common;
+
+use common::Grid;
+use raycast_dda::RayCastEngine;
+
+const MAP_SIZE: (usize, usize) = (16, 16);
+
+/// angles going all the way around in small steps
+fn angles() -> Vec<f64> {
+    (0..720).map(|i| i as f64 * std::f64::consts::TAU / 720.).collect()
+}
+
+#[test]
+fn f64_matches_f32() {
+    let engine = RayCastEngine::new(Grid::scattered(MAP_SIZE), MAP_SIZE);
+    let engine_64 = RayCastEngine::<_, f64>::with_precision(Grid::scattered(MAP_SIZE), MAP_SIZE);
+    let pos = (8.3, 8.6);
+
+    let mut matched = 0;
+    for angle in angles() {
+        let ray = engine.cast_ray((pos.0 as f32, pos.1 as f32), angle as f32, 32.);
+        let ray_64 = engine_64.cast_ray(pos, angle, 32.);
+
+        // rays that go right past a corner can go either side of it with the tiny differences in direction
+        if (ray.ray_length as f64 - ray_64.ray_length).abs() > 1e-3 {
+            continue;
+        }
+        assert_eq!(ray.hit_cell, ray_64.hit_cell);
+        assert_eq!(ray.hit_val, ray_64.hit_val);
+        assert_eq!(ray.collided_vertical, ray_64.collided_vertical);
+        assert!((ray.texture_u as f64 - ray_64.texture_u).abs() < 1e-3);
+        matched += 1;
+    }
+    assert!(matched > angles().len() * 9 / 10);
+}
+
+#[test]
+fn f64_is_precise_far_from_the_origin() {
+    // the same map a long way from the origin should give the same rays as the one at the origin
+    let near = RayCastEngine::<_, f64>::with_precision(Grid::scattered(MAP_SIZE), MAP_SIZE);
+    let mut far = RayCastEngine::<_, f64>::with_precision(Grid::scattered(MAP_SIZE), MAP_SIZE);
+    far.origin = (3e7, -5e7);
+
+    let pos = (8.3, 8.6);
+    for angle in angles() {
+        let ray = near.cast_ray(pos, angle, 32.);
+        let ray_far = far.cast_ray((pos.0 + far.origin.0, pos.1 + far.origin.1), angle, 32.);
+        assert_eq!(ray.hit_cell, ray_far.hit_cell);
+        assert!((ray.ray_length - ray_far.ray_length).abs() < 1e-6);
+        assert!((ray.texture_u - ray_far.texture_u).abs() < 1e-6);
+    }
+
+    // f32 cant even tell where in the cell the ray starts that far away
+    let mut far_32 = RayCastEngine::new(Grid::scattered(MAP_SIZE), MAP_SIZE);
+    far_32.origin = (3e7, -5e7);
+    assert_eq!(far_32.to_grid((3e7 + 8.3, -5e7 + 8.6)), (8., 8.));
+}
+
+#[test]
+fn f64_multi_matches_single() {
+    let engine = RayCastEngine::<_, f64>::with_precision(Grid::scattered(MAP_SIZE), MAP_SIZE);
+    let pos = (3.7, 12.2);
+
+    let mut rays = engine.cast_rays_multi(pos, angles(), 32.);
+    rays.sort_by_key(|(i, _)| *i);
+    for ((i, ray), angle) in rays.iter().zip(angles()) {
+        let single = engine.cast_ray(pos, angle, 32.);
+        assert_eq!(ray.ray_length.to_bits(), single.ray_length.to_bits(), "ray {i}");
+        assert_eq!(ray.hit_cell, single.hit_cell);
+    }
+}
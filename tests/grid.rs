@@ -0,0 +1,37 @@
+mod common;
+
+use common::{assert_near, Grid};
+use raycast_dda::RayCastEngine;
+use std::f32::consts::PI;
+
+const MAP_SIZE: (usize, usize) = (16, 11);
+
+#[test]
+fn rays_starting_left_of_the_map_go_into_it() {
+    let mut engine = RayCastEngine::new(Grid::room(MAP_SIZE), MAP_SIZE);
+    engine.origin = (-3., -2.);
+
+    // the ray starts half a cell left of the map, so it hits the left wall of the room after going half a cell
+    let ray = engine.cast_ray((-3.5, 3.5), 0., 30.);
+    assert_eq!((ray.hit_val, ray.hit_cell, ray.normal), (Some(1), (0, 5), (-1., 0.)));
+    assert_near(ray.ray_length, 0.5, 1e-6);
+
+    // the same as a ray inside of the map going the other way, the hit is where the wall is in world space
+    let ray = engine.cast_ray((-1.5, 3.5), PI, 30.);
+    assert_eq!((ray.hit_cell, ray.normal), ((0, 5), (1., 0.)));
+    assert_near(ray.ray_length, 0.5, 1e-6);
+    assert_near(ray.ray_position.0 + ray.ray_direction.0 * ray.ray_length, -2., 1e-6);
+}
+
+#[test]
+fn rays_starting_above_the_map_go_into_it() {
+    let mut engine = RayCastEngine::new(Grid::new(MAP_SIZE), MAP_SIZE);
+    engine.origin = (-3., -2.);
+    engine.cell_size = (2., 0.5);
+    engine.map.set(4, 0, 2);
+
+    // a quarter of a cell above the map, straight down into the top row
+    let ray = engine.cast_ray((6., -2.125), PI / 2., 30.);
+    assert_eq!((ray.hit_val, ray.hit_cell, ray.normal), (Some(2), (4, 0), (0., -1.)));
+    assert_near(ray.ray_length, 0.125, 1e-6);
+}
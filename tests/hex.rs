@@ -0,0 +1,110 @@
+mod common;
+
+use common::Rng;
+use raycast_dda::{HexMap, HexOrientation, HexRayCastEngine};
+use std::collections::HashMap;
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+const ORIENTATIONS: [HexOrientation; 2] = [HexOrientation::PointyTop, HexOrientation::FlatTop];
+
+/// a round map of hexagons out to `radius` cells from (0, 0)
+struct Hexes {
+    walls: HashMap<(i32, i32), u32>,
+    radius: i32,
+}
+
+impl HexMap for Hexes {
+    fn get_cell(&self, q: i32, r: i32) -> Option<u32> {
+        if (q.abs() + r.abs() + (q + r).abs()) / 2 > self.radius {
+            return None;
+        }
+        Some(self.walls.get(&(q, r)).copied().unwrap_or(0))
+    }
+}
+
+#[test]
+fn centers_are_in_their_own_cells() {
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+    for orientation in ORIENTATIONS {
+        for r in -8..=8 {
+            for q in -8..=8 {
+                let center = orientation.center((q, r));
+                assert_eq!(orientation.cell_at(center), (q, r), "{orientation:?}");
+
+                // anything closer to the center than the edges is in the cell too
+                let angle = rng.next() * TAU;
+                let length = rng.next() * 0.86;
+                let point = (center.0 + angle.cos() * length, center.1 + angle.sin() * length);
+                assert_eq!(orientation.cell_at(point), (q, r), "{orientation:?} {point:?}");
+            }
+        }
+    }
+}
+
+#[test]
+fn neighbouring_centers_are_one_cell_apart() {
+    for orientation in ORIENTATIONS {
+        let center = orientation.center((0, 0));
+        for neighbour in [(1, 0), (0, 1), (-1, 1), (-1, 0), (0, -1), (1, -1)] {
+            let other = orientation.center(neighbour);
+            assert!(((other.0 - center.0).hypot(other.1 - center.1) - 3f32.sqrt()).abs() < 1e-5);
+        }
+    }
+}
+
+#[test]
+fn rays_hit_the_edge_facing_them() {
+    let walls = HashMap::from([((3, 0), 2), ((0, 2), 3)]);
+    let sqrt_3 = 3f32.sqrt();
+
+    // pointy hexagons have a flat edge on their left, halfway between the centers
+    let engine = HexRayCastEngine::new(Hexes { walls: walls.clone(), radius: 6 }, HexOrientation::PointyTop);
+    let ray = engine.cast_ray((0., 0.), 0., 30.);
+    assert_eq!((ray.hit_val, ray.hit_cell, ray.collided_vertical), (Some(2), (3, 0), true));
+    assert!((ray.ray_length - 2.5 * sqrt_3).abs() < 1e-5);
+    assert!((ray.normal.0 + 1.).abs() < 1e-5 && ray.normal.1.abs() < 1e-5);
+    assert!((ray.texture_u - 0.5).abs() < 1e-5);
+
+    // flat hexagons have one on their top
+    let engine = HexRayCastEngine::new(Hexes { walls, radius: 6 }, HexOrientation::FlatTop);
+    let ray = engine.cast_ray((0., 0.), FRAC_PI_2, 30.);
+    assert_eq!((ray.hit_val, ray.hit_cell, ray.collided_vertical), (Some(3), (0, 2), false));
+    assert!((ray.ray_length - 1.5 * sqrt_3).abs() < 1e-5);
+    assert!(ray.normal.0.abs() < 1e-5 && (ray.normal.1 + 1.).abs() < 1e-5);
+}
+
+#[test]
+fn rays_hit_the_same_cells_as_marching() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    for orientation in ORIENTATIONS {
+        let mut walls = HashMap::new();
+        for r in -6..=6 {
+            for q in -6..=6 {
+                if (q, r) != (0, 0) && rng.next() < 0.15 {
+                    walls.insert((q, r), 1 + (rng.next() * 3.) as u32);
+                }
+            }
+        }
+        let engine = HexRayCastEngine::new(Hexes { walls, radius: 6 }, orientation);
+
+        for _ in 0..200 {
+            let pos = (rng.next() - 0.5, rng.next() - 0.5);
+            let angle = rng.next() * TAU;
+            let ray = engine.cast_ray(pos, angle, 100.);
+
+            // walks along the ray in tiny steps until it is in a wall or out of the map
+            let mut distance = 0.;
+            let (cell, val) = loop {
+                distance += 5e-4;
+                let cell = orientation.cell_at((pos.0 + angle.cos() * distance, pos.1 + angle.sin() * distance));
+                match engine.map.get_cell(cell.0, cell.1) {
+                    Some(0) => {}
+                    val => break (cell, val),
+                }
+            };
+
+            assert_eq!((ray.hit_cell, ray.hit_val), (cell, val), "{orientation:?} {pos:?} {angle}");
+            assert!((ray.ray_length - distance).abs() < 1e-3, "{orientation:?} {pos:?} {angle} {} {distance}", ray.ray_length);
+        }
+    }
+}
@@ -0,0 +1,49 @@
+mod common;
+
+use common::{assert_near, Grid};
+use raycast_dda::{CellShape, Layers, Map, RayCastEngine};
+
+const MAP_SIZE: (usize, usize) = (16, 11);
+
+/// a pane of glass down the middle of every cell in one column
+struct Glass {
+    column: usize,
+}
+
+impl Map for Glass {
+    fn get_cell(&self, x: usize, y: usize) -> Option<u32> {
+        if x >= MAP_SIZE.0 || y >= MAP_SIZE.1 {
+            return None;
+        }
+        Some(if x == self.column { 9 } else { 0 })
+    }
+
+    fn get_size(&self) -> (usize, usize) {
+        MAP_SIZE
+    }
+
+    fn get_shape(&self, _x: usize, _y: usize) -> CellShape {
+        CellShape::Thin { vertical: true, offset: 0.5 }
+    }
+}
+
+#[test]
+fn layers_of_different_map_types_are_all_hit() {
+    let (room, glass) = (Grid::room(MAP_SIZE), Glass { column: 7 });
+    let layers: Vec<&(dyn Map + Sync)> = vec![&room, &glass];
+    let engine = RayCastEngine::new(Layers::new(layers), MAP_SIZE);
+
+    // the glass doesnt stop the ray from going on to the wall of the room
+    let hits = engine.cast_ray_layers((2.5, 5.5), 0., 30.);
+    assert_eq!(hits.len(), 2);
+    assert_eq!((hits[0].layer, hits[0].hit_val, hits[0].hit_cell), (1, Some(9), (7, 5)));
+    assert_near(hits[0].ray_length, 5., 1e-6);
+    assert_eq!((hits[1].layer, hits[1].hit_val, hits[1].hit_cell), (0, Some(1), (15, 5)));
+    assert_near(hits[1].ray_length, 12.5, 1e-6);
+
+    // going the other way only the room is in front of the ray
+    let hits = engine.cast_ray_layers((2.5, 5.5), std::f32::consts::PI, 30.);
+    assert_eq!(hits.len(), 1);
+    assert_eq!((hits[0].layer, hits[0].hit_cell), (0, (0, 5)));
+    assert_near(hits[0].ray_length, 1.5, 1e-6);
+}
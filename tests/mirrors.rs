@@ -0,0 +1,47 @@
+mod common;
+
+use common::{assert_near, Grid};
+use raycast_dda::RayCastEngine;
+
+const MAP_SIZE: (usize, usize) = (16, 11);
+
+/// two mirrors facing each other 5 apart, with the ray starting 1.5 from the left one
+fn facing_mirrors(max_bounces: u32) -> RayCastEngine<Grid> {
+    let mut grid = Grid::room(MAP_SIZE);
+    grid.set(3, 5, 4);
+    grid.set_mirror(3, 5);
+    grid.set(9, 5, 5);
+    grid.set_mirror(9, 5);
+    let mut engine = RayCastEngine::new(grid, MAP_SIZE);
+    engine.max_bounces = max_bounces;
+    engine
+}
+
+#[test]
+fn rays_stop_bouncing_when_they_run_out_of_bounces() {
+    let engine = facing_mirrors(3);
+
+    // bounces off the right mirror at 3.5, the left at 8.5 and the right again at 13.5, then the left one acts like a wall
+    let ray = engine.cast_ray((5.5, 5.5), 0., 100.);
+    assert_eq!((ray.hit_val, ray.hit_cell, ray.bounces), (Some(4), (3, 5), 3));
+    assert_near(ray.ray_length, 18.5, 1e-5);
+    assert_eq!((ray.normal, ray.local_direction), ((1., 0.), (-1., 0.)));
+
+    let (segments, _) = engine.cast_ray_path((5.5, 5.5), 0., 100.);
+    let lengths: Vec<_> = segments.iter().map(|segment| (segment.start_length, segment.end_length)).collect();
+    assert_eq!(lengths, [(0., 3.5), (3.5, 8.5), (8.5, 13.5), (13.5, 18.5)]);
+    assert_eq!((segments[1].start, segments[1].end), ((9., 5.5), (4., 5.5)));
+}
+
+#[test]
+fn mirrors_without_any_bounces_are_walls() {
+    let ray = facing_mirrors(0).cast_ray((5.5, 5.5), 0., 100.);
+    assert_eq!((ray.hit_val, ray.hit_cell, ray.bounces), (Some(5), (9, 5), 0));
+    assert_near(ray.ray_length, 3.5, 1e-6);
+}
+
+#[test]
+fn rays_between_mirrors_still_stop_at_the_max_distance() {
+    let ray = facing_mirrors(100).cast_ray((5.5, 5.5), 0., 10.);
+    assert_eq!((ray.hit_val, ray.bounces), (None, 2));
+}
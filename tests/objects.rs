@@ -0,0 +1,103 @@
+mod common;
+
+use common::{assert_near, Grid};
+use raycast_dda::{Object, ObjectHit, ObjectShape, Portal, RayCastEngine, RayData, RayHit};
+
+const MAP_SIZE: (usize, usize) = (16, 11);
+
+fn object(hit: RayHit) -> ObjectHit {
+    match hit {
+        RayHit::Object(hit) => hit,
+        RayHit::Wall(ray) => panic!("hit the wall at {:?}", ray.hit_cell),
+    }
+}
+
+fn wall(hit: RayHit) -> RayData {
+    match hit {
+        RayHit::Wall(ray) => ray,
+        RayHit::Object(hit) => panic!("hit object {}", hit.id),
+    }
+}
+
+#[test]
+fn objects_in_front_of_walls_are_hit_first() {
+    let mut grid = Grid::room(MAP_SIZE);
+    grid.set(7, 7, 2);
+    let engine = RayCastEngine::new(grid, MAP_SIZE);
+    let objects = [
+        Object { id: 1, shape: ObjectShape::Circle { center: (6.5, 5.5), radius: 0.5 } },
+        Object { id: 2, shape: ObjectShape::Aabb { min: (8., 3.), max: (9., 4.) } },
+        Object { id: 3, shape: ObjectShape::Aabb { min: (9., 7.), max: (10., 8.) } },
+    ];
+
+    let hit = object(engine.cast_ray_objects((2.5, 5.5), 0., 30., &objects));
+    assert_eq!((hit.id, hit.normal), (1, (-1., 0.)));
+    assert_near(hit.ray_length, 3.5, 1e-6);
+    assert_near(hit.point.0, 6., 1e-6);
+
+    let hit = object(engine.cast_ray_objects((2.5, 3.5), 0., 30., &objects));
+    assert_eq!((hit.id, hit.normal, hit.point), (2, (-1., 0.), (8., 3.5)));
+    assert_near(hit.ray_length, 5.5, 1e-6);
+
+    // the box behind the wall is hidden
+    let ray = wall(engine.cast_ray_objects((2.5, 7.5), 0., 30., &objects));
+    assert_eq!(ray.hit_cell, (7, 7));
+    assert_near(ray.ray_length, 4.5, 1e-6);
+
+    // and so are objects past the max distance
+    let ray = wall(engine.cast_ray_objects((2.5, 5.5), 0., 3., &objects));
+    assert_eq!(ray.hit_val, None);
+
+    // rays from the top hit the top of the circle
+    let hit = object(engine.cast_ray_objects((6.5, 1.5), std::f32::consts::FRAC_PI_2, 30., &objects));
+    assert_near(hit.ray_length, 3.5, 1e-6);
+    assert_near(hit.normal.1, -1., 1e-6);
+}
+
+#[test]
+fn objects_are_hit_on_both_sides_of_portals() {
+    let mut grid = Grid::room(MAP_SIZE);
+    grid.set_portal(5, 5, Portal { target: (10, 2), rotation: 1 });
+    let engine = RayCastEngine::new(grid, MAP_SIZE);
+
+    // a circle sticking out of the portal cell is hit before the ray goes through
+    let straddling = [Object { id: 1, shape: ObjectShape::Circle { center: (5.2, 5.5), radius: 0.4 } }];
+    let hit = object(engine.cast_ray_objects((2.5, 5.5), 0., 30., &straddling));
+    assert_near(hit.ray_length, 2.3, 1e-5);
+
+    // the rest of the portal cell is never seen, the ray comes out of the top of (10, 2) going down
+    let inside = [Object { id: 2, shape: ObjectShape::Circle { center: (5.6, 5.5), radius: 0.3 } }];
+    let ray = wall(engine.cast_ray_objects((2.5, 5.5), 0., 30., &inside));
+    assert_eq!(ray.hit_cell, (10, 10));
+
+    let past = [Object { id: 3, shape: ObjectShape::Circle { center: (10.5, 4.5), radius: 0.25 } }];
+    let hit = object(engine.cast_ray_objects((2.5, 5.5), 0., 30., &past));
+    assert_near(hit.ray_length, 4.75, 1e-5);
+    assert_near(hit.point.0, 10.5, 1e-5);
+    assert_near(hit.point.1, 4.25, 1e-5);
+    assert_near(hit.normal.1, -1., 1e-5);
+}
+
+#[test]
+fn objects_are_hit_on_both_sides_of_mirrors() {
+    let mut grid = Grid::room(MAP_SIZE);
+    grid.set(9, 5, 4);
+    grid.set_mirror(9, 5);
+    let engine = RayCastEngine::new(grid, MAP_SIZE);
+
+    // a circle half in front of the mirror is hit before the ray gets to it
+    let straddling = [Object { id: 1, shape: ObjectShape::Circle { center: (8.9, 5.5), radius: 0.3 } }];
+    let hit = object(engine.cast_ray_objects((5.5, 5.5), 0., 30., &straddling));
+    assert_near(hit.ray_length, 3.1, 1e-5);
+
+    // one behind where the ray started is seen in the mirror, facing it
+    let behind = [Object { id: 2, shape: ObjectShape::Circle { center: (4.5, 5.5), radius: 0.25 } }];
+    let hit = object(engine.cast_ray_objects((5.5, 5.5), 0., 30., &behind));
+    assert_near(hit.ray_length, 7.75, 1e-5);
+    assert_near(hit.normal.0, 1., 1e-5);
+
+    // a box lined up with the mirror and behind it is never hit
+    let hidden = [Object { id: 3, shape: ObjectShape::Aabb { min: (10., 5.), max: (11., 6.) } }];
+    let ray = wall(engine.cast_ray_objects((5.5, 5.5), 0., 30., &hidden));
+    assert_eq!((ray.hit_cell, ray.bounces), ((0, 5), 1));
+}
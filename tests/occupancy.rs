@@ -0,0 +1,171 @@
+mod common;
+
+use common::Grid;
+use raycast_dda::{Map, OccupancyGrid, RangeSensor, RayCastEngine, Reading};
+use std::f32::consts::TAU;
+
+const MAP_SIZE: (usize, usize) = (20, 20);
+
+/// a room with walls around the edge and a pillar in the middle
+fn pillar_room() -> Grid {
+    let mut grid = Grid::room(MAP_SIZE);
+    for y in 9..11 {
+        for x in 9..11 {
+            grid.set(x, y, 1);
+        }
+    }
+    grid
+}
+
+#[test]
+fn beams_free_the_cells_they_go_through() {
+    let mut engine = RayCastEngine::new(OccupancyGrid::new(MAP_SIZE), MAP_SIZE);
+    engine.insert_beam((2.5, 5.5), 0., 4.5, true);
+
+    let grid = &engine.map;
+    for x in 2..7 {
+        assert_eq!(grid.log_odds(x, 5), Some(grid.miss), "cell {x}");
+    }
+    assert_eq!(grid.log_odds(7, 5), Some(grid.hit));
+    assert_eq!(grid.log_odds(8, 5), Some(0.));
+    assert_eq!(grid.get_cell(7, 5), Some(1));
+    assert_eq!(grid.get_cell(6, 5), Some(0));
+    assert!((grid.probability(7, 5).unwrap() - 0.7006).abs() < 1e-3);
+
+    // beams that didnt hit anything only free cells
+    engine.insert_beam((2.5, 12.5), 0., 4.5, false);
+    assert_eq!(engine.map.log_odds(7, 12), Some(0.));
+}
+
+#[test]
+fn beams_that_arent_finite_are_left_out() {
+    let mut engine = RayCastEngine::new(OccupancyGrid::new(MAP_SIZE), MAP_SIZE);
+    engine.insert_beam((2.5, 5.5), f32::NAN, 4.5, true);
+    engine.insert_beam((2.5, 5.5), f32::INFINITY, 4.5, true);
+    engine.insert_beam((2.5, 5.5), 0., f32::NAN, true);
+    engine.insert_beam((2.5, 5.5), 0., f32::INFINITY, false);
+    engine.insert_beam((f32::NAN, 5.5), 0., 4.5, true);
+    engine.insert_beam((2.5, f32::NEG_INFINITY), 0., 4.5, true);
+
+    for y in 0..MAP_SIZE.1 {
+        for x in 0..MAP_SIZE.0 {
+            assert_eq!(engine.map.log_odds(x, y), Some(0.));
+        }
+    }
+}
+
+#[test]
+fn long_beams_only_walk_the_grid() {
+    let mut engine = RayCastEngine::new(OccupancyGrid::new(MAP_SIZE), MAP_SIZE);
+    engine.insert_beam((2.5, 5.5), 0., 1e9, true);
+    engine.insert_beam((2.5, 7.5), 0., 1e9, false);
+
+    // beams starting outside of the grid are picked up where they go into it
+    engine.insert_beam((-1e8, 9.5), 0., 2e8, false);
+    engine.insert_beam((-5.5, 11.5), 0., 8., true);
+    engine.insert_beam((-5.5, 13.5), std::f32::consts::PI, 1e9, true);
+
+    let grid = &engine.map;
+    for x in 0..MAP_SIZE.0 {
+        assert_eq!(grid.log_odds(x, 5), Some(if x < 2 { 0. } else { grid.miss }), "cell {x}");
+        assert_eq!(grid.log_odds(x, 7), Some(if x < 2 { 0. } else { grid.miss }), "cell {x}");
+        assert_eq!(grid.log_odds(x, 9), Some(grid.miss), "cell {x}");
+        assert_eq!(grid.log_odds(x, 11), Some([grid.miss, grid.miss, grid.hit, 0.][x.min(3)]), "cell {x}");
+        assert_eq!(grid.log_odds(x, 13), Some(0.), "cell {x}");
+    }
+}
+
+#[test]
+fn log_odds_stay_within_the_limits() {
+    let mut engine = RayCastEngine::new(OccupancyGrid::new(MAP_SIZE), MAP_SIZE);
+    for _ in 0..100 {
+        engine.insert_beam((2.5, 5.5), 0., 4.5, true);
+    }
+    assert_eq!(engine.map.log_odds(7, 5), Some(engine.map.limits.1));
+    assert_eq!(engine.map.log_odds(3, 5), Some(engine.map.limits.0));
+}
+
+#[test]
+fn scans_map_the_room() {
+    let room = RayCastEngine::new(pillar_room(), MAP_SIZE);
+    let mut engine = RayCastEngine::new(OccupancyGrid::new(MAP_SIZE), MAP_SIZE);
+    let mut sensor = RangeSensor::new(720, TAU, 0., 40.);
+
+    for pos in [(4.5, 4.5), (15.5, 4.5), (4.5, 15.5), (15.5, 15.5)] {
+        let scan = sensor.scan(&room, pos, 0.);
+        engine.insert_scan(&scan);
+    }
+
+    // every cell that was seen agrees with the room
+    let mut seen = 0;
+    for y in 0..MAP_SIZE.1 {
+        for x in 0..MAP_SIZE.0 {
+            if engine.map.log_odds(x, y) != Some(0.) {
+                assert_eq!(engine.map.get_cell(x, y), room.map.get_cell(x, y), "cell ({x}, {y})");
+                seen += 1;
+            }
+        }
+    }
+    assert!(seen > MAP_SIZE.0 * MAP_SIZE.1 * 9 / 10);
+
+    // so rays casted through the grid hit the same walls as ones casted through the room
+    for i in 0..360 {
+        let angle = i as f32 * TAU / 360.;
+        let ray = engine.cast_ray((6.3, 13.1), angle, 40.);
+        let expected = room.cast_ray((6.3, 13.1), angle, 40.);
+        assert_eq!(ray.hit_cell, expected.hit_cell, "angle {angle}");
+        assert_eq!(ray.ray_length, expected.ray_length);
+    }
+}
+
+#[test]
+fn scans_free_cells_when_nothing_is_in_range() {
+    let room = RayCastEngine::new(pillar_room(), MAP_SIZE);
+    let mut engine = RayCastEngine::new(OccupancyGrid::new(MAP_SIZE), MAP_SIZE);
+
+    // every wall is further away than the max range so none of the rays hit anything
+    let mut sensor = RangeSensor::new(360, TAU, 0., 4.);
+    let scan = sensor.scan(&room, (5.5, 14.5), 0.);
+    assert!(scan.ranges.iter().all(|reading| *reading == Reading::MaxRange));
+    engine.insert_scan(&scan);
+
+    for y in 0..MAP_SIZE.1 {
+        for x in 0..MAP_SIZE.0 {
+            let distance = (x as f32 + 0.5 - 5.5).hypot(y as f32 + 0.5 - 14.5);
+            let log_odds = engine.map.log_odds(x, y).unwrap();
+            if distance < 3. {
+                assert!(log_odds < 0., "cell ({x}, {y})");
+            } else if distance > 5. {
+                assert_eq!(log_odds, 0., "cell ({x}, {y})");
+            }
+        }
+    }
+
+    // rays that didnt come back dont change anything
+    let mut engine = RayCastEngine::new(OccupancyGrid::new(MAP_SIZE), MAP_SIZE);
+    sensor.noise.dropout = 1.;
+    let scan = sensor.scan(&room, (5.5, 14.5), 0.);
+    assert!(scan.ranges.iter().all(|reading| *reading == Reading::Dropout));
+    engine.insert_scan(&scan);
+    assert!((0..MAP_SIZE.1).all(|y| (0..MAP_SIZE.0).all(|x| engine.map.log_odds(x, y) == Some(0.))));
+}
+
+#[test]
+fn scans_work_in_world_space() {
+    let mut room = RayCastEngine::new(pillar_room(), MAP_SIZE);
+    room.cell_size = (0.5, 0.25);
+    room.origin = (-3., 2.);
+
+    let mut engine = RayCastEngine::new(OccupancyGrid::new(MAP_SIZE), MAP_SIZE);
+    engine.cell_size = room.cell_size;
+    engine.origin = room.origin;
+
+    let mut sensor = RangeSensor::new(720, TAU, 0., 40.);
+    let scan = sensor.scan(&room, room.to_world((4.5, 4.5)), 0.);
+    engine.insert_scan(&scan);
+
+    assert_eq!(engine.map.get_cell(0, 4), Some(1));
+    assert_eq!(engine.map.get_cell(9, 9), Some(1));
+    assert_eq!(engine.map.get_cell(5, 5), Some(0));
+    assert_eq!(engine.map.get_cell(8, 8), Some(0));
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use common::{Grid, Rng};
+use raycast_dda::{CellShape, Portal, RayCastEngine, RayData};
+
+const MAP_SIZE: (usize, usize) = (48, 40);
+const MAX_DISTANCE: f32 = 80.;
+
+/// a room with scattered walls along with a thin wall, a portal and a mirror for the rays that stop in a packet to finish
+fn engine(rng: &mut Rng) -> RayCastEngine<Grid> {
+    let mut grid = Grid::room(MAP_SIZE);
+    for y in 1..MAP_SIZE.1 - 1 {
+        for x in 1..MAP_SIZE.0 - 1 {
+            if rng.next() < 0.02 {
+                grid.set(x, y, 1 + (rng.next() * 3.) as u32);
+            }
+        }
+    }
+    grid.set(20, 20, 4);
+    grid.set_shape(20, 20, CellShape::Thin { vertical: true, offset: 0.3 });
+    grid.set(24, 12, 0);
+    grid.set_portal(24, 12, Portal { target: (10, 30), rotation: 3 });
+    grid.set(30, 25, 5);
+    grid.set_mirror(30, 25);
+    RayCastEngine::new(grid, MAP_SIZE)
+}
+
+/// checks two rays are exactly the same, down to the bits of their floats
+fn assert_same(a: &RayData, b: &RayData) {
+    assert_eq!(a.ray_length.to_bits(), b.ray_length.to_bits());
+    assert_eq!(a.texture_u.to_bits(), b.texture_u.to_bits());
+    assert_eq!(a.exit_length.to_bits(), b.exit_length.to_bits());
+    assert_eq!(a.ray_angle.to_bits(), b.ray_angle.to_bits());
+    assert_eq!((a.hit_cell, a.hit_val, a.collided_vertical), (b.hit_cell, b.hit_val, b.collided_vertical));
+    assert_eq!((a.normal, a.bounces, a.transform), (b.normal, b.bounces, b.transform));
+    assert_eq!((a.local_origin, a.local_direction), (b.local_origin, b.local_direction));
+}
+
+/// somewhere to cast from and the angles of neighbouring columns on the screen looking a random way
+fn view(rng: &mut Rng, columns: usize) -> ((f32, f32), Vec<f32>) {
+    let pos = (1. + rng.next() * (MAP_SIZE.0 - 2) as f32, 1. + rng.next() * (MAP_SIZE.1 - 2) as f32);
+    let angle = rng.next() * std::f32::consts::TAU;
+    (pos, (0..columns).map(|x| angle + x as f32 * 0.002).collect())
+}
+
+#[test]
+fn packets_give_the_same_rays_as_casting_one_at_a_time() {
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+    let engine = engine(&mut rng);
+
+    for _ in 0..300 {
+        let (pos, angles) = view(&mut rng, 8);
+        let packet_4 = engine.cast_ray_packet4(pos, angles[..4].try_into().unwrap(), MAX_DISTANCE);
+        let packet_8 = engine.cast_ray_packet8(pos, angles[..].try_into().unwrap(), MAX_DISTANCE);
+
+        for (i, angle) in angles.iter().enumerate() {
+            let ray = engine.cast_ray(pos, *angle, MAX_DISTANCE);
+            if i < 4 {
+                assert_same(&packet_4[i], &ray);
+            }
+            assert_same(&packet_8[i], &ray);
+        }
+    }
+
+    // rays starting in the cell with the thin wall and ones running out of distance part way
+    for max_distance in [0.5, 3., 7.3] {
+        let angles: [f32; 8] = std::array::from_fn(|i| i as f32 * 0.8);
+        let packet = engine.cast_ray_packet8((20.5, 20.5), angles, max_distance);
+        for (ray, angle) in packet.iter().zip(angles) {
+            assert_same(ray, &engine.cast_ray((20.5, 20.5), angle, max_distance));
+        }
+    }
+}
+
+#[test]
+fn rays_left_over_from_packets_are_the_same_as_casting_one_at_a_time() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    let engine = engine(&mut rng);
+
+    // counts that arent a multiple of the packet sizes leave rays over at the end
+    for columns in [1, 3, 4, 5, 7, 8, 9, 13, 16, 17, 31, 100] {
+        let (pos, angles) = view(&mut rng, columns);
+        let mut rays = engine.cast_rays_multi(pos, angles.clone(), MAX_DISTANCE);
+        rays.sort_by_key(|(i, _)| *i);
+
+        assert_eq!(rays.len(), columns);
+        for ((i, ray), (j, angle)) in rays.iter().zip(angles.iter().enumerate()) {
+            assert_eq!(*i, j);
+            assert_same(ray, &engine.cast_ray(pos, *angle, MAX_DISTANCE));
+        }
+    }
+}
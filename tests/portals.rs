@@ -0,0 +1,51 @@
+mod common;
+
+use common::{assert_near, Grid};
+use raycast_dda::{Portal, PortalTransform, RayCastEngine};
+
+const MAP_SIZE: (usize, usize) = (16, 11);
+
+#[test]
+fn rays_come_out_of_turned_portals_going_the_new_way() {
+    let mut grid = Grid::room(MAP_SIZE);
+    grid.set_portal(5, 5, Portal { target: (10, 2), rotation: 1 });
+    grid.set(10, 6, 3);
+    let engine = RayCastEngine::new(grid, MAP_SIZE);
+
+    // the ray goes into the left of the portal 2.5 along, comes out of the top of (10, 2) going down
+    // and goes another 4 to the wall at (10, 6)
+    let ray = engine.cast_ray((2.5, 5.5), 0., 30.);
+    assert_eq!((ray.hit_val, ray.hit_cell, ray.collided_vertical), (Some(3), (10, 6), false));
+    assert_near(ray.ray_length, 6.5, 1e-5);
+    assert_near(ray.texture_u, 0.5, 1e-5);
+    assert_eq!(ray.normal, (0., -1.));
+
+    // the quarter turn takes the center of the portal to the center of the target
+    assert_eq!(ray.transform, PortalTransform { rotation: 1, offset: (16., -3.) });
+    assert_eq!(ray.local_direction, (0., 1.));
+    assert_eq!(ray.local_origin, (10.5, -0.5));
+    let end = (ray.local_origin.0 + ray.local_direction.0 * ray.ray_length, ray.local_origin.1 + ray.local_direction.1 * ray.ray_length);
+    assert_eq!(end, (10.5, 6.));
+
+    // rays that miss the portal dont get turned
+    let ray = engine.cast_ray((2.5, 4.5), 0., 30.);
+    assert_eq!((ray.hit_cell, ray.transform), ((15, 4), PortalTransform::default()));
+    assert_near(ray.ray_length, 12.5, 1e-5);
+}
+
+#[test]
+fn rays_come_out_of_half_turned_portals_going_back() {
+    let mut grid = Grid::room(MAP_SIZE);
+    grid.set_portal(5, 5, Portal { target: (12, 8), rotation: 2 });
+    let engine = RayCastEngine::new(grid, MAP_SIZE);
+
+    // coming out of the right of (12, 8) going left, the wall at x = 1 is 12 more away
+    let ray = engine.cast_ray((2.5, 5.3), 0., 30.);
+    assert_eq!((ray.hit_cell, ray.normal, ray.local_direction), ((0, 8), (1., 0.), (-1., 0.)));
+    assert_near(ray.ray_length, 14.5, 1e-5);
+
+    // the side of the ray is flipped too, so it comes out lower down in the target
+    let hit = (ray.local_origin.0 + ray.local_direction.0 * ray.ray_length, ray.local_origin.1 + ray.local_direction.1 * ray.ray_length);
+    assert_near(hit.0, 1., 1e-5);
+    assert_near(hit.1, 8.7, 1e-5);
+}
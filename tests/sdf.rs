@@ -0,0 +1,147 @@
+mod common;
+
+use common::{Grid, Rng};
+use raycast_dda::{Map, SignedDistanceField};
+
+const MAP_SIZE: (usize, usize) = (24, 17);
+
+/// if a cell is a wall, everything outside of the map is
+fn is_solid(grid: &Grid, x: i32, y: i32) -> bool {
+    x < 0 || y < 0 || grid.get_cell(x as usize, y as usize).is_none_or(|val| val > 0)
+}
+
+/// a map with a few solid blocks, including a big one so there are corners deep inside of walls
+fn make_map(rng: &mut Rng) -> Grid {
+    let mut grid = Grid::new(MAP_SIZE);
+    for y in 0..MAP_SIZE.1 {
+        for x in 0..MAP_SIZE.0 {
+            if rng.next() < 0.05 || (15..21).contains(&x) && (4..11).contains(&y) {
+                grid.set(x, y, 1);
+            }
+        }
+    }
+    grid
+}
+
+/// the distance from a point to the nearest wall cell or, inside of walls, to the nearest empty cell.
+/// looks at every cell along with a ring of cells around the map, which are all walls
+fn brute_force(grid: &Grid, point: (f32, f32)) -> f32 {
+    let inside = is_solid(grid, point.0.floor() as i32, point.1.floor() as i32);
+    let mut nearest = f32::INFINITY;
+    for y in -1..=MAP_SIZE.1 as i32 {
+        for x in -1..=MAP_SIZE.0 as i32 {
+            if is_solid(grid, x, y) == inside {
+                continue;
+            }
+            let dx = (x as f32 - point.0).max(point.0 - (x + 1) as f32).max(0.);
+            let dy = (y as f32 - point.1).max(point.1 - (y + 1) as f32).max(0.);
+            nearest = nearest.min((dx * dx + dy * dy).sqrt());
+        }
+    }
+    if inside {
+        -nearest
+    } else {
+        nearest
+    }
+}
+
+fn assert_corners_exact(field: &SignedDistanceField, grid: &Grid) {
+    for y in 0..=MAP_SIZE.1 {
+        for x in 0..=MAP_SIZE.0 {
+            let expected = brute_force(grid, (x as f32, y as f32)).max(0.);
+            // corners on the edge of a wall are 0, the brute force only sees the cell below and right of them
+            let distance = field.corner(x, y).unwrap();
+            if distance >= 0. {
+                assert!((distance - expected).abs() < 1e-5, "corner ({x}, {y}) is {distance} not {expected}");
+            } else {
+                let expected = brute_force(grid, (x as f32 + 0.5, y as f32 + 0.5));
+                assert!(expected < 0. && distance <= -1. + 1e-5, "corner ({x}, {y}) is {distance}");
+            }
+        }
+    }
+}
+
+#[test]
+fn corners_are_exact() {
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+    for _ in 0..4 {
+        let grid = make_map(&mut rng);
+        let field = SignedDistanceField::new(&grid, MAP_SIZE);
+        assert_corners_exact(&field, &grid);
+    }
+}
+
+#[test]
+fn distances_inside_walls_are_negative() {
+    let mut grid = Grid::new(MAP_SIZE);
+    for y in 4..11 {
+        for x in 5..12 {
+            grid.set(x, y, 1);
+        }
+    }
+    let field = SignedDistanceField::new(&grid, MAP_SIZE);
+
+    // the middle of the block is 3.5 cells from every side
+    assert!((field.distance((8.5, 7.5)) + 3.).abs() < 1e-5);
+    assert!((field.corner(8, 7).unwrap() + 3.).abs() < 1e-5);
+    assert_eq!(field.corner(5, 4), Some(0.));
+    assert!((field.distance((3.5, 7.5)) - 1.5).abs() < 1e-5);
+
+    // the edge of the map is a wall too
+    assert_eq!(field.distance((0., 0.)), 0.);
+    assert!((field.distance((2., 2.)) - 2.).abs() < 1e-5);
+}
+
+#[test]
+fn distances_blend_between_corners() {
+    let mut rng = Rng(7);
+    let grid = make_map(&mut rng);
+    let field = SignedDistanceField::new(&grid, MAP_SIZE);
+
+    for _ in 0..1000 {
+        let point = (rng.next() * MAP_SIZE.0 as f32, rng.next() * MAP_SIZE.1 as f32);
+
+        // the real distance changes by at most 1 for every unit moved, so the blend of
+        // the corners is never farther off than the distance to the middle of a cell
+        let expected = brute_force(&grid, point);
+        let distance = field.distance(point);
+        assert!((distance - expected).abs() <= std::f32::consts::FRAC_1_SQRT_2 + 1e-5, "{point:?} is {distance} not {expected}");
+
+        // the gradient is the slope of the blend, away from the edges of cells where it jumps
+        let fract = (point.0.fract(), point.1.fract());
+        if fract.0 > 0.01 && fract.0 < 0.99 && fract.1 > 0.01 && fract.1 < 0.99 {
+            let gradient = field.gradient(point);
+            let slope = (
+                (field.distance((point.0 + 0.005, point.1)) - field.distance((point.0 - 0.005, point.1))) / 0.01,
+                (field.distance((point.0, point.1 + 0.005)) - field.distance((point.0, point.1 - 0.005))) / 0.01,
+            );
+            assert!((gradient.0 - slope.0).abs() < 1e-2 && (gradient.1 - slope.1).abs() < 1e-2, "{point:?} has a gradient of {gradient:?} not {slope:?}");
+        }
+    }
+}
+
+#[test]
+fn updates_match_building_again() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    let mut grid = make_map(&mut rng);
+    let mut field = SignedDistanceField::new(&grid, MAP_SIZE);
+
+    for _ in 0..30 {
+        // flips a few cells at a time, sometimes setting cells to the value they already have
+        let cells: Vec<(usize, usize)> = (0..3)
+            .map(|_| ((rng.next() * MAP_SIZE.0 as f32) as usize, (rng.next() * MAP_SIZE.1 as f32) as usize))
+            .collect();
+        for &(x, y) in &cells {
+            grid.set(x, y, (rng.next() < 0.5) as u32);
+        }
+        field.update(&grid, cells);
+
+        let rebuilt = SignedDistanceField::new(&grid, MAP_SIZE);
+        for y in 0..=MAP_SIZE.1 {
+            for x in 0..=MAP_SIZE.0 {
+                assert_eq!(field.corner(x, y), rebuilt.corner(x, y), "corner ({x}, {y})");
+            }
+        }
+    }
+    assert_corners_exact(&field, &grid);
+}
@@ -0,0 +1,120 @@
+mod common;
+
+use common::Grid;
+use raycast_dda::{RangeSensor, RayCastEngine, Reading, SensorNoise};
+use std::f32::consts::{PI, TAU};
+
+const MAP_SIZE: (usize, usize) = (20, 20);
+
+fn assert_angles(sensor: &RangeSensor, expected: &[f32]) {
+    let angles = sensor.angles();
+    assert_eq!(angles.len(), expected.len());
+    for (angle, expected) in angles.iter().zip(expected) {
+        assert!((angle - expected).abs() < 1e-6, "{angles:?} isnt {expected:?}");
+    }
+}
+
+#[test]
+fn rays_are_spread_over_the_arc() {
+    assert_angles(&RangeSensor::new(5, PI, 0., 10.), &[-PI / 2., -PI / 4., 0., PI / 4., PI / 2.]);
+
+    // a whole turn doesnt cast the ray at the back twice
+    assert_angles(&RangeSensor::new(4, TAU, 0., 10.), &[-PI, -PI / 2., 0., PI / 2.]);
+
+    assert_eq!(RangeSensor::new(1, PI, 0., 10.).angles(), vec![0.]);
+}
+
+#[test]
+fn noiseless_scans_match_cast_ray() {
+    let engine = RayCastEngine::new(Grid::room(MAP_SIZE), MAP_SIZE);
+    let mut sensor = RangeSensor::new(90, TAU, 0.1, 30.);
+    let (pos, heading) = ((7.5, 12.25), 0.4);
+
+    let scan = sensor.scan(&engine, pos, heading);
+    for (angle, range) in scan.angles.iter().zip(&scan.ranges) {
+        let ray = engine.cast_ray(pos, heading + angle, 30.);
+        assert_eq!(*range, Reading::Hit(ray.ray_length));
+    }
+
+    // every point is on the inside of the walls
+    for point in scan.points() {
+        let on_wall = [point.0 - 1., 19. - point.0, point.1 - 1., 19. - point.1].iter().any(|d| d.abs() < 1e-3);
+        assert!(on_wall, "{point:?} isnt on a wall");
+    }
+}
+
+#[test]
+fn ranges_outside_the_limits_arent_measured() {
+    let engine = RayCastEngine::new(Grid::room(MAP_SIZE), MAP_SIZE);
+    let pos = (2.5, 10.);
+
+    // the wall to the left is 1.5 away and the one to the right is 16.5 away. walls that are too
+    // close dont come back at all, but ones that are too far away mean nothing is in range
+    let mut sensor = RangeSensor::new(1, 0., 2., 30.);
+    assert_eq!(sensor.scan(&engine, pos, PI).ranges, vec![Reading::Dropout]);
+    let mut sensor = RangeSensor::new(1, 0., 0., 10.);
+    assert_eq!(sensor.scan(&engine, pos, 0.).ranges, vec![Reading::MaxRange]);
+    assert_eq!(sensor.scan(&engine, pos, PI).ranges, vec![Reading::Hit(1.5)]);
+
+    // rays that leave the map without hitting anything are the same as walls that are too far away
+    let engine = RayCastEngine::new(Grid::new(MAP_SIZE), MAP_SIZE);
+    assert_eq!(sensor.scan(&engine, pos, 0.).ranges, vec![Reading::MaxRange]);
+}
+
+#[test]
+fn local_points_are_relative_to_the_sensor() {
+    let engine = RayCastEngine::new(Grid::room(MAP_SIZE), MAP_SIZE);
+    let mut sensor = RangeSensor::new(1, 0., 0., 30.);
+
+    // facing up the map at the wall 4 away, which is straight ahead of the sensor
+    let scan = sensor.scan(&engine, (10., 5.), -PI / 2.);
+    let local = scan.local_points();
+    assert_eq!(local.len(), 1);
+    assert!((local[0].0 - 4.).abs() < 1e-5 && local[0].1.abs() < 1e-5);
+
+    let world = scan.points();
+    assert!((world[0].0 - 10.).abs() < 1e-5 && (world[0].1 - 1.).abs() < 1e-5);
+}
+
+#[test]
+fn noise_is_the_same_for_the_same_seed() {
+    let engine = RayCastEngine::new(Grid::room(MAP_SIZE), MAP_SIZE);
+    let noise = SensorNoise {
+        range_stddev: 0.05,
+        range_stddev_per_unit: 0.01,
+        dropout: 0.1,
+    };
+
+    let mut scans = Vec::new();
+    for seed in [7, 7, 8] {
+        let mut sensor = RangeSensor::new(360, TAU, 0., 30.);
+        sensor.noise = noise;
+        sensor.seed(seed);
+        scans.push(sensor.scan(&engine, (10., 10.), 0.).ranges);
+    }
+
+    assert_eq!(scans[0], scans[1]);
+    assert_ne!(scans[0], scans[2]);
+}
+
+#[test]
+fn noise_has_the_right_spread() {
+    let engine = RayCastEngine::new(Grid::room(MAP_SIZE), MAP_SIZE);
+    let mut sensor = RangeSensor::new(2000, 0., 0., 30.);
+    sensor.noise = SensorNoise {
+        range_stddev: 0.1,
+        range_stddev_per_unit: 0.,
+        dropout: 0.25,
+    };
+    sensor.seed(1);
+
+    // every ray goes straight at the wall 9 away
+    let ranges: Vec<f32> = sensor.scan(&engine, (10., 10.), 0.).ranges.into_iter().filter_map(Reading::range).collect();
+    let dropped = 1. - ranges.len() as f32 / 2000.;
+    assert!((dropped - 0.25).abs() < 0.04, "dropped {dropped}");
+
+    let mean = ranges.iter().sum::<f32>() / ranges.len() as f32;
+    let stddev = (ranges.iter().map(|r| (r - mean) * (r - mean)).sum::<f32>() / ranges.len() as f32).sqrt();
+    assert!((mean - 9.).abs() < 0.02, "mean {mean}");
+    assert!((stddev - 0.1).abs() < 0.01, "stddev {stddev}");
+}
@@ -0,0 +1,119 @@
+mod common;
+
+use common::{assert_near, Grid};
+use raycast_dda::{CellShape, RayCastEngine};
+use std::f32::consts::{FRAC_1_SQRT_2, FRAC_PI_2, PI};
+
+const MAP_SIZE: (usize, usize) = (16, 11);
+
+#[test]
+fn thin_walls_are_hit_partway_across_their_cell() {
+    let mut grid = Grid::room(MAP_SIZE);
+    grid.set(6, 5, 1);
+    grid.set_shape(6, 5, CellShape::Thin { vertical: true, offset: 0.5 });
+    grid.set(4, 8, 2);
+    grid.set_shape(4, 8, CellShape::Thin { vertical: false, offset: 0.25 });
+    let engine = RayCastEngine::new(grid, MAP_SIZE);
+
+    let ray = engine.cast_ray((2.5, 5.5), 0., 30.);
+    assert_eq!((ray.hit_val, ray.hit_cell, ray.normal, ray.collided_vertical), (Some(1), (6, 5), (-1., 0.), true));
+    assert_near(ray.ray_length, 4., 1e-6);
+    assert_near(ray.texture_u, 0.5, 1e-6);
+
+    // a ray going up and to the right meets the wall at y = 5.8
+    let ray = engine.cast_ray((2.5, 5.5), 0.3f32.atan2(4.), 30.);
+    assert_eq!(ray.hit_cell, (6, 5));
+    assert_near(ray.ray_length, 4f32.hypot(0.3), 1e-5);
+    assert_near(ray.texture_u, 0.8, 1e-5);
+
+    // the wall is hit from inside of its own cell, but not once the ray has started past it
+    let ray = engine.cast_ray((6.2, 5.5), 0., 30.);
+    assert_eq!(ray.hit_cell, (6, 5));
+    assert_near(ray.ray_length, 0.3, 1e-6);
+    let ray = engine.cast_ray((6.7, 5.5), 0., 30.);
+    assert_eq!(ray.hit_cell, (15, 5));
+    assert_near(ray.ray_length, 8.3, 1e-5);
+
+    // horizontal walls are hit from below with their normal facing back down
+    let ray = engine.cast_ray((4.5, 9.5), -FRAC_PI_2, 30.);
+    assert_eq!((ray.hit_val, ray.hit_cell, ray.normal, ray.collided_vertical), (Some(2), (4, 8), (0., 1.), false));
+    assert_near(ray.ray_length, 1.25, 1e-6);
+}
+
+#[test]
+fn rays_go_through_the_open_part_of_doors() {
+    let mut grid = Grid::room(MAP_SIZE);
+    grid.set(9, 3, 1);
+    grid.set_shape(9, 3, CellShape::Door { vertical: true, offset: 0.5, open: 0.5 });
+    grid.set(9, 7, 1);
+    grid.set_shape(9, 7, CellShape::Door { vertical: true, offset: 0.5, open: 0. });
+    let engine = RayCastEngine::new(grid, MAP_SIZE);
+
+    // the top half of the half open door has slid out of the cell so rays go past to the room wall
+    let ray = engine.cast_ray((2.5, 3.3), 0., 30.);
+    assert_eq!(ray.hit_cell, (15, 3));
+    assert_near(ray.ray_length, 12.5, 1e-5);
+
+    // the bottom half is still there, the texture slides along with the door
+    let ray = engine.cast_ray((2.5, 3.7), 0., 30.);
+    assert_eq!((ray.hit_cell, ray.normal), ((9, 3), (-1., 0.)));
+    assert_near(ray.ray_length, 7., 1e-5);
+    assert_near(ray.texture_u, 0.2, 1e-5);
+
+    // a closed door is a thin wall
+    let ray = engine.cast_ray((2.5, 7.2), 0., 30.);
+    assert_eq!(ray.hit_cell, (9, 7));
+    assert_near(ray.ray_length, 7., 1e-5);
+    assert_near(ray.texture_u, 0.2, 1e-5);
+}
+
+#[test]
+fn rays_only_just_touching_a_circle_still_hit_it() {
+    let mut grid = Grid::room(MAP_SIZE);
+    grid.set(8, 5, 3);
+    grid.set_shape(8, 5, CellShape::Circle { center: (0.5, 0.5), radius: 0.25 });
+    let engine = RayCastEngine::new(grid, MAP_SIZE);
+
+    // straight at the middle hits the near side of the circle
+    let ray = engine.cast_ray((2.5, 5.5), 0., 30.);
+    assert_eq!((ray.hit_val, ray.hit_cell), (Some(3), (8, 5)));
+    assert_near(ray.ray_length, 5.75, 1e-6);
+    assert_near(ray.normal.0, -1., 1e-6);
+    assert_near(ray.texture_u, 0.5, 1e-6);
+
+    // a ray along the top of the circle touches it right above the middle, with the normal facing up
+    let ray = engine.cast_ray((2.5, 5.25), 0., 30.);
+    assert_eq!(ray.hit_cell, (8, 5));
+    assert_near(ray.ray_length, 6., 1e-5);
+    assert_near(ray.normal.1, -1., 1e-5);
+
+    // any farther out misses and goes on to the room wall
+    let ray = engine.cast_ray((2.5, 5.24), 0., 30.);
+    assert_eq!(ray.hit_cell, (15, 5));
+    assert_near(ray.ray_length, 12.5, 1e-5);
+}
+
+#[test]
+fn diagonal_segments_are_hit_where_the_ray_crosses_them() {
+    let mut grid = Grid::room(MAP_SIZE);
+    grid.set(5, 8, 1);
+    grid.set_shape(5, 8, CellShape::Segment { start: (0., 0.), end: (1., 1.) });
+    let engine = RayCastEngine::new(grid, MAP_SIZE);
+
+    // the segment goes down to the right so the lower the ray the farther it goes into the cell
+    for (y, length) in [(8.2, 2.7), (8.7, 3.2)] {
+        let ray = engine.cast_ray((2.5, y), 0., 30.);
+        assert_eq!(ray.hit_cell, (5, 8));
+        assert_near(ray.ray_length, length, 1e-5);
+        assert_near(ray.texture_u, y - 8., 1e-5);
+        assert_near(ray.normal.0, -FRAC_1_SQRT_2, 1e-6);
+        assert_near(ray.normal.1, FRAC_1_SQRT_2, 1e-6);
+    }
+
+    // from the other side the normal flips around
+    let ray = engine.cast_ray((7.5, 8.3), PI, 30.);
+    assert_eq!(ray.hit_cell, (5, 8));
+    assert_near(ray.ray_length, 2.2, 1e-5);
+    assert_near(ray.normal.0, FRAC_1_SQRT_2, 1e-6);
+    assert_near(ray.normal.1, -FRAC_1_SQRT_2, 1e-6);
+}
@@ -0,0 +1,102 @@
+mod common;
+
+use common::{assert_near, Grid};
+use raycast_dda::{RayCastEngine, RayData, SpanKind};
+
+const MAP_SIZE: (usize, usize) = (16, 11);
+
+fn spans_of(spans: &[RayData], kind: SpanKind) -> Vec<&RayData> {
+    spans.iter().filter(|span| span.span_kind == kind).collect()
+}
+
+#[test]
+fn rays_see_over_short_walls() {
+    let mut grid = Grid::room(MAP_SIZE);
+    grid.set(5, 5, 1);
+    grid.set_height(5, 5, 0.25);
+    grid.set(8, 5, 2);
+    grid.set_height(8, 5, 2.);
+    let engine = RayCastEngine::new(grid, MAP_SIZE);
+
+    let spans = engine.cast_ray_spans((2.5, 5.5), 0., 30., 0.5);
+    let walls = spans_of(&spans, SpanKind::Wall);
+    assert_eq!(walls.len(), 2);
+
+    // the whole short wall can be seen, 2.5 away
+    assert_eq!((walls[0].hit_cell, walls[0].hit_val, walls[0].wall_span), ((5, 5), Some(1), (0., 0.25)));
+    assert_near(walls[0].ray_length, 2.5, 1e-6);
+    assert_near(walls[0].view_slopes.0, -0.5 / 2.5, 1e-6);
+    assert_near(walls[0].view_slopes.1, -0.25 / 2.5, 1e-6);
+
+    // the bottom of the tall wall is hidden behind the far edge of the top of the short one
+    assert_eq!((walls[1].hit_cell, walls[1].hit_val, walls[1].wall_span), ((8, 5), Some(2), (0., 2.)));
+    assert_near(walls[1].ray_length, 5.5, 1e-6);
+    assert_near(walls[1].view_slopes.0, -0.25 / 3.5, 1e-6);
+    assert_near(walls[1].view_slopes.1, 1.5 / 5.5, 1e-6);
+
+    // the top of the short wall is seen between its near and far edges
+    let top = spans_of(&spans, SpanKind::Floor).into_iter().find(|span| span.hit_cell == (5, 5)).unwrap();
+    assert_eq!(top.wall_span, (0.25, 0.25));
+    assert_near(top.view_slopes.0, -0.25 / 2.5, 1e-6);
+    assert_near(top.view_slopes.1, -0.25 / 3.5, 1e-6);
+
+    // the room wall behind is shorter than the part of the tall wall above the camera so it is hidden
+    assert!(spans.iter().all(|span| span.hit_cell.0 <= 8));
+
+    // a plain ray stops at the first wall no matter how short it is
+    let ray = engine.cast_ray((2.5, 5.5), 0., 30.);
+    assert_eq!((ray.hit_cell, ray.ray_length), ((5, 5), 2.5));
+}
+
+#[test]
+fn tall_walls_hide_shorter_ones_behind_them() {
+    let mut grid = Grid::room(MAP_SIZE);
+    grid.set(6, 5, 1);
+    grid.set_height(6, 5, 3.);
+    let engine = RayCastEngine::new(grid, MAP_SIZE);
+
+    // the room wall behind is inside the slopes hidden by the tall wall, only the open sky above is left
+    let spans = engine.cast_ray_spans((2.5, 5.5), 0., 30., 0.5);
+    let walls = spans_of(&spans, SpanKind::Wall);
+    assert_eq!(walls.len(), 1);
+    assert_eq!(walls[0].hit_cell, (6, 5));
+    assert_near(walls[0].view_slopes.1, 2.5 / 3.5, 1e-6);
+    assert!(spans.iter().all(|span| span.hit_cell.0 <= 6));
+}
+
+#[test]
+fn steps_between_floors_and_ceilings_are_seen() {
+    let mut grid = Grid::room(MAP_SIZE);
+    for y in 0..MAP_SIZE.1 {
+        for x in 0..MAP_SIZE.0 {
+            // a raised floor from x = 6 and a lower ceiling from x = 9
+            grid.set_floor(x, y, if x >= 6 { 0.3 } else { 0. });
+            grid.set_ceiling(x, y, if x >= 9 { 0.9 } else { 1.2 });
+        }
+    }
+    let engine = RayCastEngine::new(grid, MAP_SIZE);
+
+    let spans = engine.cast_ray_spans((2.5, 5.5), 0., 30., 0.5);
+
+    // only the part of the step sticking out above the lower floor is reported
+    let floor_steps = spans_of(&spans, SpanKind::FloorStep);
+    assert_eq!(floor_steps.len(), 1);
+    assert_eq!((floor_steps[0].hit_cell, floor_steps[0].wall_span), ((6, 5), (0., 0.3)));
+    assert_near(floor_steps[0].ray_length, 3.5, 1e-6);
+    assert_near(floor_steps[0].view_slopes.0, -0.5 / 3.5, 1e-6);
+    assert_near(floor_steps[0].view_slopes.1, -0.2 / 3.5, 1e-6);
+
+    let ceiling_steps = spans_of(&spans, SpanKind::CeilingStep);
+    assert_eq!(ceiling_steps.len(), 1);
+    assert_eq!(ceiling_steps[0].hit_cell, (9, 5));
+    assert_near(ceiling_steps[0].wall_span.0, 0.9, 1e-6);
+    assert_near(ceiling_steps[0].wall_span.1, 1.2, 1e-6);
+    assert_near(ceiling_steps[0].view_slopes.0, 0.4 / 6.5, 1e-6);
+    assert_near(ceiling_steps[0].view_slopes.1, 0.7 / 6.5, 1e-6);
+
+    // the floor of the raised cells is at its own height and the ceilings are seen from below
+    let floor = spans_of(&spans, SpanKind::Floor).into_iter().find(|span| span.hit_cell == (7, 5)).unwrap();
+    assert_eq!(floor.wall_span, (0.3, 0.3));
+    let ceiling = spans_of(&spans, SpanKind::Ceiling).into_iter().find(|span| span.hit_cell == (3, 5)).unwrap();
+    assert_eq!(ceiling.wall_span, (1.2, 1.2));
+}
@@ -0,0 +1,122 @@
+mod common;
+
+use common::{Grid, Rng};
+use raycast_dda::{Object, ObjectShape, RayCastEngine, RayHit, SpatialHash};
+use std::collections::HashSet;
+
+const MAP_SIZE: (usize, usize) = (24, 17);
+
+fn random_object(rng: &mut Rng, id: usize) -> Object {
+    let center = (1. + rng.next() * (MAP_SIZE.0 - 2) as f32, 1. + rng.next() * (MAP_SIZE.1 - 2) as f32);
+    let shape = if rng.next() < 0.5 {
+        ObjectShape::Circle { center, radius: 0.1 + rng.next() * 1.2 }
+    } else {
+        let half = (0.1 + rng.next() * 1.2, 0.1 + rng.next() * 1.2);
+        ObjectShape::Aabb { min: (center.0 - half.0, center.1 - half.1), max: (center.0 + half.0, center.1 + half.1) }
+    };
+    Object { id, shape }
+}
+
+/// inserts, moves and removes objects at random, returning the objects that are left
+fn shuffled_hash(rng: &mut Rng, hash: &mut SpatialHash) -> Vec<Option<Object>> {
+    let mut objects = vec![None; 60];
+    for _ in 0..300 {
+        let id = (rng.next() * objects.len() as f32) as usize;
+        if rng.next() < 0.25 {
+            assert_eq!(hash.remove(id), objects[id].take());
+        } else {
+            let object = random_object(rng, id);
+            hash.insert(object);
+            objects[id] = Some(object);
+        }
+    }
+    assert_eq!(hash.len(), objects.iter().flatten().count());
+    objects
+}
+
+#[test]
+fn cells_hold_every_object_that_covers_them() {
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+    let mut hash = SpatialHash::new((1.5, 0.75), (-2., 1.));
+    let objects = shuffled_hash(&mut rng, &mut hash);
+
+    for y in -4..30 {
+        for x in -4..30 {
+            let found: HashSet<usize> = hash.objects_in((x, y)).map(|object| object.id).collect();
+
+            // scans every object for ones whose bounds cover the cell
+            let expected: HashSet<usize> = objects
+                .iter()
+                .flatten()
+                .filter(|object| {
+                    let (min, max) = object.shape.bounds();
+                    let (min, max) = (hash.cell_at(min), hash.cell_at(max));
+                    min.0 <= x && x <= max.0 && min.1 <= y && y <= max.1
+                })
+                .map(|object| object.id)
+                .collect();
+            assert_eq!(found, expected, "{x} {y}");
+        }
+    }
+
+    for (id, object) in objects.iter().enumerate() {
+        assert_eq!(hash.get(id), *object);
+    }
+}
+
+#[test]
+fn rays_hit_the_same_objects_as_testing_every_one() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    let mut grid = Grid::room(MAP_SIZE);
+    for y in 1..MAP_SIZE.1 - 1 {
+        for x in 1..MAP_SIZE.0 - 1 {
+            if rng.next() < 0.05 {
+                grid.set(x, y, 1);
+            }
+        }
+    }
+    let engine = RayCastEngine::new(grid, MAP_SIZE);
+    let mut hash = SpatialHash::new((1., 1.), (0., 0.));
+    let objects: Vec<Object> = shuffled_hash(&mut rng, &mut hash).into_iter().flatten().collect();
+
+    for _ in 0..500 {
+        let pos = (1. + rng.next() * (MAP_SIZE.0 - 2) as f32, 1. + rng.next() * (MAP_SIZE.1 - 2) as f32);
+        let angle = rng.next() * 10.;
+        match (engine.cast_ray_spatial_hash(pos, angle, 40., &hash), engine.cast_ray_objects(pos, angle, 40., &objects)) {
+            (RayHit::Object(hashed), RayHit::Object(every)) => {
+                // objects the same distance away can be picked in either order
+                assert!(hashed.id == every.id || hashed.ray_length == every.ray_length, "{pos:?} {angle}");
+                assert_eq!((hashed.ray_length, hashed.point), (every.ray_length, every.point));
+            }
+            (RayHit::Wall(hashed), RayHit::Wall(every)) => assert_eq!((hashed.hit_cell, hashed.ray_length), (every.hit_cell, every.ray_length)),
+            _ => panic!("{pos:?} {angle} hit different things"),
+        }
+    }
+}
+
+#[test]
+fn rays_that_miss_everything_stop_when_max_distance_is_infinite() {
+    let engine = RayCastEngine::new(Grid::new((8, 8)), (8, 8));
+    let mut hash = SpatialHash::new((1., 1.), (0., 0.));
+    let objects = [
+        Object { id: 0, shape: ObjectShape::Circle { center: (2., 2.), radius: 0.5 } },
+        Object { id: 1, shape: ObjectShape::Aabb { min: (30., -20.), max: (31., -19.) } },
+    ];
+    for object in objects {
+        hash.insert(object);
+    }
+
+    for max_distance in [f32::INFINITY, 1e8] {
+        for angle in [0., 1., std::f32::consts::PI] {
+            let hashed = engine.cast_ray_spatial_hash((4., 6.), angle, max_distance, &hash);
+            assert!(matches!(hashed, RayHit::Wall(ray) if ray.hit_val.is_none()), "{angle}");
+            assert!(matches!(engine.cast_ray_objects((4., 6.), angle, max_distance, &objects), RayHit::Wall(_)));
+        }
+    }
+
+    // the bounds shrink back once the far away object is gone, and rays still find the one thats left
+    hash.remove(1);
+    let angle = f32::atan2(2. - 6., 2. - 4.);
+    assert!(matches!(engine.cast_ray_spatial_hash((4., 6.), angle, f32::INFINITY, &hash), RayHit::Object(hit) if hit.id == 0));
+    assert!(matches!(engine.cast_ray_spatial_hash((4., 6.), 0., f32::INFINITY, &hash), RayHit::Wall(_)));
+}
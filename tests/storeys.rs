@@ -0,0 +1,67 @@
+mod common;
+
+use common::Grid;
+use raycast_dda::{RayCastEngine, Storeys};
+
+const MAP_SIZE: (usize, usize) = (16, 11);
+
+/// three storeys 2 apart. the bottom one has a ceiling at 1.5 in (3, 3) and is open to the sky in (4, 3), the
+/// middle one has a floor 0.2 up and a ceiling at 1 in (3, 3) and a wall at (6, 5)
+fn storeys() -> Storeys<Grid> {
+    let mut bottom = Grid::room(MAP_SIZE);
+    bottom.set_ceiling(3, 3, 1.5);
+    let mut middle = Grid::room(MAP_SIZE);
+    middle.set_floor(3, 3, 0.2);
+    middle.set_ceiling(3, 3, 1.);
+    middle.set(6, 5, 2);
+    let top = Grid::room(MAP_SIZE);
+    Storeys::new(vec![bottom, middle, top], 2.)
+}
+
+#[test]
+fn things_are_in_the_storey_whose_floor_is_under_them() {
+    let storeys = storeys();
+
+    assert_eq!(storeys.find_storey(3, 3, 1.), (0, 0., 1.5));
+    assert_eq!(storeys.find_storey(3, 3, 3.), (1, 2.2, 3.));
+    assert_eq!(storeys.find_storey(3, 3, 5.), (2, 4., f32::INFINITY));
+
+    // inside the slab between a ceiling and the floor above is still in the storey below
+    assert_eq!(storeys.find_storey(3, 3, 2.1), (0, 0., 1.5));
+}
+
+#[test]
+fn open_ceilings_let_the_storey_below_go_all_the_way_up() {
+    let storeys = storeys();
+
+    // the storeys above dont have a floor in the cell so everything up there is part of the bottom one
+    assert_eq!(storeys.find_storey(4, 3, 1.), (0, 0., f32::INFINITY));
+    assert_eq!(storeys.find_storey(4, 3, 3.), (0, 0., f32::INFINITY));
+    assert_eq!(storeys.find_storey(4, 3, 100.), (0, 0., f32::INFINITY));
+}
+
+#[test]
+fn rays_only_look_at_their_own_storey() {
+    let engine = RayCastEngine::new(storeys(), MAP_SIZE);
+
+    let ray = engine.cast_ray_on_layer(1, (2.5, 5.5), 0., 30.);
+    assert_eq!((ray.layer, ray.hit_val, ray.hit_cell, ray.ray_length), (1, Some(2), (6, 5), 3.5));
+
+    let ray = engine.cast_ray_on_layer(0, (2.5, 5.5), 0., 30.);
+    assert_eq!((ray.layer, ray.hit_val, ray.hit_cell, ray.ray_length), (0, Some(1), (15, 5), 12.5));
+}
+
+#[test]
+fn storeys_that_dont_exist_cant_be_seen_from() {
+    let engine = RayCastEngine::new(storeys(), MAP_SIZE);
+
+    let ray = engine.cast_ray_on_layer(3, (2.5, 5.5), 0., 30.);
+    assert_eq!((ray.layer, ray.hit_val, ray.ray_length, ray.ray_position), (3, None, 0., (2.5, 5.5)));
+
+    let rays = engine.cast_rays_multi_on_layer(7, (2.5, 5.5), vec![0., 1., 2.], 30.);
+    assert_eq!(rays.len(), 3);
+    assert!(rays.iter().all(|(_, ray)| ray.hit_val.is_none() && ray.ray_length == 0.));
+
+    assert!(engine.cast_ray_spans_on_layer(3, (2.5, 5.5), 0., 30., 0.5).is_empty());
+    assert!(engine.cast_rays_spans_multi_on_layer(3, (2.5, 5.5), vec![0., 1.], 30., 0.5).iter().all(|(_, spans)| spans.is_empty()));
+}
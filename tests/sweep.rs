@@ -0,0 +1,139 @@
+mod common;
+
+use common::{Grid, Rng};
+use raycast_dda::{Map, RayCastEngine};
+use std::f32::consts::{FRAC_PI_4, PI};
+
+const MAP_SIZE: (usize, usize) = (24, 17);
+
+/// checks if a box overlaps any walls, only touching them doesnt count
+fn overlaps_wall(grid: &Grid, center: (f32, f32), half: (f32, f32)) -> bool {
+    let (min, max) = ((center.0 - half.0, center.1 - half.1), (center.0 + half.0, center.1 + half.1));
+    (min.1.floor() as i32..max.1.ceil() as i32).any(|y| {
+        (min.0.floor() as i32..max.0.ceil() as i32).any(|x| grid.get_cell(x as usize, y as usize).is_none_or(|val| val > 0))
+    })
+}
+
+#[test]
+fn boxes_stop_when_they_touch_a_wall() {
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+    let mut grid = Grid::room(MAP_SIZE);
+    for y in 1..MAP_SIZE.1 - 1 {
+        for x in 1..MAP_SIZE.0 - 1 {
+            if rng.next() < 0.1 {
+                grid.set(x, y, 1 + (rng.next() * 3.) as u32);
+            }
+        }
+    }
+    let engine = RayCastEngine::new(grid, MAP_SIZE);
+
+    let mut tested = 0;
+    while tested < 500 {
+        let pos = (1. + rng.next() * (MAP_SIZE.0 - 2) as f32, 1. + rng.next() * (MAP_SIZE.1 - 2) as f32);
+        let half = (0.05 + rng.next() * 0.4, 0.05 + rng.next() * 0.4);
+        if overlaps_wall(&engine.map, pos, half) {
+            continue;
+        }
+        tested += 1;
+
+        let angle = rng.next() * 10.;
+        let dir = (angle.cos(), angle.sin());
+        let hit = engine.cast_box(pos, angle, (half.0 * 2., half.1 * 2.), 30.);
+        let at = |distance: f32| (pos.0 + dir.0 * distance, pos.1 + dir.1 * distance);
+
+        // the box fits right up until it stops, then goes into the wall it hit
+        let val = hit.hit_val.unwrap();
+        assert!(!overlaps_wall(&engine.map, at(hit.distance - 1e-3), half), "{pos:?} {angle} {hit:?}");
+        let (min, max) = {
+            let center = at(hit.distance + 1e-3);
+            ((center.0 - half.0, center.1 - half.1), (center.0 + half.0, center.1 + half.1))
+        };
+        let cell = hit.hit_cell;
+        assert!((cell.0 as f32) < max.0 && (cell.0 + 1) as f32 > min.0 && (cell.1 as f32) < max.1 && (cell.1 + 1) as f32 > min.1);
+        assert_eq!(engine.map.get_cell(cell.0 as usize, cell.1 as usize), Some(val));
+
+        // the normal points back along the way the box came
+        assert!(hit.normal.0 * dir.0 + hit.normal.1 * dir.1 < 0.);
+        assert!((hit.position.0 - at(hit.distance).0).abs() < 1e-4 && (hit.position.1 - at(hit.distance).1).abs() < 1e-4);
+
+        // a box is never farther than a ray from its middle
+        let ray = engine.cast_ray(pos, angle, 30.);
+        assert!(hit.distance <= ray.ray_length + 1e-4);
+    }
+}
+
+#[test]
+fn boxes_dont_fit_through_diagonal_gaps() {
+    let mut grid = Grid::room(MAP_SIZE);
+    grid.set(6, 5, 1);
+    grid.set(5, 6, 2);
+    let engine = RayCastEngine::new(grid, MAP_SIZE);
+
+    // the box touches the wall on its right when it has gone 0.45 along each axis, before it gets to the one below
+    let hit = engine.cast_box((5.5, 5.3), FRAC_PI_4, (0.1, 0.1), 10.);
+    assert_eq!(hit.hit_val, Some(1));
+    assert_eq!(hit.hit_cell, (6, 5));
+    assert_eq!(hit.normal, (-1., 0.));
+    assert!((hit.distance - 0.45 * 2f32.sqrt()).abs() < 1e-5);
+}
+
+#[test]
+fn boxes_slide_along_walls() {
+    let mut grid = Grid::room(MAP_SIZE);
+    for x in 3..20 {
+        grid.set(x, 4, 1);
+        grid.set(x, 6, 1);
+    }
+    grid.set(12, 5, 3);
+    let engine = RayCastEngine::new(grid, MAP_SIZE);
+
+    // a box as tall as the corridor goes down it until the wall at the end
+    let hit = engine.cast_box((2.5, 5.5), 0., (1., 1.), 30.);
+    assert_eq!(hit.hit_val, Some(3));
+    assert_eq!(hit.hit_cell, (12, 5));
+    assert!((hit.distance - 9.).abs() < 1e-5);
+    assert_eq!(hit.position, (11.5, 5.5));
+
+    // one that is any taller hits the corner of the corridor
+    let hit = engine.cast_box((2.5, 5.5), 0., (1., 1.01), 30.);
+    assert_eq!(hit.hit_cell.0, 3);
+    assert!((hit.distance - 0.).abs() < 1e-5);
+
+    // nothing stops it going back out of the corridor until the wall of the room
+    let hit = engine.cast_box((11.5, 5.5), PI, (1., 1.), 30.);
+    assert_eq!(hit.hit_cell, (0, 5));
+    assert!((hit.distance - 10.).abs() < 1e-5);
+}
+
+#[test]
+fn boxes_starting_in_walls_stop_straight_away() {
+    let mut grid = Grid::room(MAP_SIZE);
+    grid.set(8, 8, 2);
+    let engine = RayCastEngine::new(grid, MAP_SIZE);
+
+    let hit = engine.cast_box((7.8, 8.5), 1., (0.5, 0.5), 30.);
+    assert_eq!((hit.distance, hit.hit_val, hit.hit_cell, hit.normal), (0., Some(2), (8, 8), (0., 0.)));
+
+    // boxes only touching a wall can still move away from it
+    let hit = engine.cast_box((7.75, 8.5), PI, (0.5, 0.5), 30.);
+    assert_eq!((hit.hit_cell, hit.normal), ((0, 8), (1., 0.)));
+    assert!((hit.distance - 6.5).abs() < 1e-5);
+}
+
+#[test]
+fn boxes_are_stretched_with_the_cells() {
+    let mut grid = Grid::room(MAP_SIZE);
+    grid.set(10, 3, 1);
+    let mut engine = RayCastEngine::new(grid, MAP_SIZE);
+    engine.cell_size = (2., 0.5);
+    engine.origin = (-3., 1.);
+
+    // the front of the box gets to the wall at x = 17 when its middle is at 16.5
+    let hit = engine.cast_box((5., 2.75), 0., (1., 0.2), 40.);
+    assert_eq!(hit.hit_cell, (10, 3));
+    assert!((hit.distance - 11.5).abs() < 1e-4);
+    assert_eq!(hit.normal, (-1., 0.));
+
+    let hit = engine.cast_box((5., 2.75), 0., (1., 0.2), 4.);
+    assert_eq!((hit.hit_val, hit.distance), (None, 4.));
+}
@@ -0,0 +1,146 @@
+mod common;
+
+use common::Rng;
+use raycast_dda::{RayCastEngine3D, VoxelFace, VoxelMap};
+
+const MAP_SIZE: (usize, usize, usize) = (8, 7, 6);
+
+struct Voxels {
+    cells: Vec<u32>,
+}
+
+impl VoxelMap for Voxels {
+    fn get_voxel(&self, x: usize, y: usize, z: usize) -> Option<u32> {
+        self.cells.get((z * MAP_SIZE.1 + y) * MAP_SIZE.0 + x).copied()
+    }
+
+    fn get_size(&self) -> (usize, usize, usize) {
+        MAP_SIZE
+    }
+}
+
+impl Voxels {
+    fn empty() -> Self {
+        Voxels { cells: vec![0; MAP_SIZE.0 * MAP_SIZE.1 * MAP_SIZE.2] }
+    }
+
+    fn set(&mut self, x: usize, y: usize, z: usize, val: u32) {
+        self.cells[(z * MAP_SIZE.1 + y) * MAP_SIZE.0 + x] = val;
+    }
+
+    fn at(&self, point: (f32, f32, f32)) -> Option<u32> {
+        let voxel = (point.0.floor(), point.1.floor(), point.2.floor());
+        let size = (MAP_SIZE.0 as f32, MAP_SIZE.1 as f32, MAP_SIZE.2 as f32);
+        if voxel.0 < 0. || voxel.1 < 0. || voxel.2 < 0. || voxel.0 >= size.0 || voxel.1 >= size.1 || voxel.2 >= size.2 {
+            return None;
+        }
+        self.get_voxel(voxel.0 as usize, voxel.1 as usize, voxel.2 as usize)
+    }
+}
+
+/// walks along a ray in tiny steps until it is in a wall or out of the map
+fn march(voxels: &Voxels, pos: (f32, f32, f32), dir: (f32, f32, f32)) -> (f32, Option<u32>) {
+    let step = 1e-4;
+    let mut distance = 0.;
+    loop {
+        distance += step;
+        let point = (pos.0 + dir.0 * distance, pos.1 + dir.1 * distance, pos.2 + dir.2 * distance);
+        match voxels.at(point) {
+            Some(0) => {}
+            val => return (distance, val),
+        }
+    }
+}
+
+#[test]
+fn rays_hit_the_same_voxels_as_marching() {
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+    let mut voxels = Voxels::empty();
+    for z in 0..MAP_SIZE.2 {
+        for y in 0..MAP_SIZE.1 {
+            for x in 0..MAP_SIZE.0 {
+                if rng.next() < 0.15 {
+                    voxels.set(x, y, z, 1 + (rng.next() * 3.) as u32);
+                }
+            }
+        }
+    }
+    voxels.set(4, 3, 3, 0);
+    let engine = RayCastEngine3D::new(voxels, MAP_SIZE);
+
+    for _ in 0..200 {
+        let pos = (4. + rng.next(), 3. + rng.next(), 3. + rng.next());
+        let direction = (rng.next() - 0.5, rng.next() - 0.5, rng.next() - 0.5);
+        let ray = engine.cast_ray(pos, direction, 100.);
+
+        let (distance, val) = march(&engine.map, pos, ray.ray_direction);
+        assert_eq!(ray.hit_val, val, "{pos:?} {direction:?}");
+        assert!((ray.ray_length - distance).abs() < 2e-4, "{pos:?} {direction:?} {} {distance}", ray.ray_length);
+
+        // the hit point is on the face that was hit, and the voxel in front of it is empty
+        let point = ray.hit_point();
+        let normal = ray.face.normal();
+        let front = (point.0 + normal.0 as f32 * 1e-3, point.1 + normal.1 as f32 * 1e-3, point.2 + normal.2 as f32 * 1e-3);
+        assert_eq!(engine.map.at(front), Some(0));
+        let adjacent = ray.adjacent_voxel();
+        assert_eq!(engine.map.at((adjacent.0 as f32 + 0.5, adjacent.1 as f32 + 0.5, adjacent.2 as f32 + 0.5)), Some(0));
+    }
+}
+
+#[test]
+fn rays_hit_the_face_facing_them() {
+    let mut voxels = Voxels::empty();
+    voxels.set(6, 2, 2, 1);
+    voxels.set(2, 5, 2, 2);
+    voxels.set(2, 2, 0, 3);
+    voxels.set(4, 4, 4, 4);
+    let engine = RayCastEngine3D::new(voxels, MAP_SIZE);
+    let pos = (2.5, 2.5, 2.5);
+
+    let ray = engine.cast_ray(pos, (1., 0., 0.), 100.);
+    assert_eq!((ray.hit_val, ray.hit_voxel, ray.face, ray.ray_length), (Some(1), (6, 2, 2), VoxelFace::NegX, 3.5));
+    assert_eq!((ray.face.normal(), ray.adjacent_voxel()), ((-1, 0, 0), (5, 2, 2)));
+
+    let ray = engine.cast_ray(pos, (0., 2., 0.), 100.);
+    assert_eq!((ray.hit_val, ray.hit_voxel, ray.face, ray.ray_length), (Some(2), (2, 5, 2), VoxelFace::NegY, 2.5));
+
+    let ray = engine.cast_ray(pos, (0., 0., -1.), 100.);
+    assert_eq!((ray.hit_val, ray.hit_voxel, ray.face, ray.ray_length), (Some(3), (2, 2, 0), VoxelFace::PosZ, 1.5));
+    assert_eq!(ray.face.normal(), (0, 0, 1));
+
+    // a diagonal ray goes through the corner of the voxel, it enters it through the face on the last axis it crosses
+    let ray = engine.cast_ray((2.5, 2.6, 2.7), (1., 1., 1.), 100.);
+    assert_eq!((ray.hit_val, ray.hit_voxel, ray.face), (Some(4), (4, 4, 4), VoxelFace::NegX));
+    assert!((ray.ray_length - 1.5 * 3f32.sqrt()).abs() < 1e-5);
+
+    // coming from the other side it hits the opposite faces
+    let ray = engine.cast_ray((5.5, 5.4, 5.3), (-1., -1., -1.), 100.);
+    assert_eq!((ray.hit_val, ray.hit_voxel, ray.face), (Some(4), (4, 4, 4), VoxelFace::PosX));
+    assert!((ray.ray_length - 0.5 * 3f32.sqrt()).abs() < 1e-5);
+}
+
+#[test]
+fn rays_without_a_direction_dont_go_anywhere() {
+    let engine = RayCastEngine3D::new(Voxels::empty(), MAP_SIZE);
+
+    for direction in [(0., 0., 0.), (f32::NAN, 0., 1.)] {
+        let ray = engine.cast_ray((1.5, 1.5, 1.5), direction, 100.);
+        assert_eq!((ray.hit_val, ray.ray_length, ray.hit_voxel), (None, 0., (0, 0, 0)));
+    }
+}
+
+#[test]
+fn rays_stop_at_the_edge_of_the_map() {
+    let engine = RayCastEngine3D::new(Voxels::empty(), MAP_SIZE);
+
+    // the voxel just outside of the map is given without a value
+    let ray = engine.cast_ray((1.5, 2.5, 3.5), (0., 0., 1.), 100.);
+    assert_eq!((ray.hit_val, ray.hit_voxel, ray.face, ray.ray_length), (None, (1, 2, 6), VoxelFace::NegZ, 2.5));
+
+    let ray = engine.cast_ray((1.5, 2.5, 3.5), (-1., 0., 0.), 100.);
+    assert_eq!((ray.hit_val, ray.hit_voxel, ray.face, ray.ray_length), (None, (-1, 2, 3), VoxelFace::PosX, 1.5));
+
+    // rays that run out of distance first stop at the first voxel edge past it, still in the map
+    let ray = engine.cast_ray((1.5, 2.5, 3.5), (1., 0., 0.), 2.);
+    assert_eq!((ray.hit_val, ray.hit_voxel, ray.ray_length), (None, (4, 2, 3), 2.5));
+}